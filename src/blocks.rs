@@ -0,0 +1,318 @@
+use std::{fmt, ops::RangeInclusive, path::Path, str::FromStr};
+
+use serde::Serialize;
+
+/// Unicode 版本，如 `15.0`、`15.1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnicodeVersion(pub u8, pub u8);
+
+impl UnicodeVersion {
+    /// 登記表已收錄的最新版本
+    pub const LATEST: UnicodeVersion = UnicodeVersion(17, 0);
+}
+
+impl FromStr for UnicodeVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("無效的 Unicode 版本: {}", s);
+        let mut parts = s.trim().split('.');
+        let major = parts.next().ok_or_else(err)?.parse().map_err(|_| err())?;
+        let minor = match parts.next() {
+            Some(minor) => minor.parse().map_err(|_| err())?,
+            None => 0,
+        };
+        // 忽略修訂號，如 `15.0.0`
+        if parts
+            .next()
+            .is_some_and(|patch| patch.parse::<u8>().is_err())
+        {
+            return Err(err());
+        }
+
+        Ok(UnicodeVersion(major, minor))
+    }
+}
+
+impl fmt::Display for UnicodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.0, self.1)
+    }
+}
+
+impl Serialize for UnicodeVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockKind {
+    /// 統一表意文字及其擴展區
+    Unified,
+    /// 兼容表意文字
+    Compatibility,
+    /// 部首
+    Radicals,
+    /// 筆畫
+    Strokes,
+    /// 表意文字描述字符
+    Description,
+}
+
+/// 一個與中日韓表意文字相關的區塊
+#[derive(Debug, PartialEq, Eq)]
+pub struct Block {
+    /// 命令行中使用的名稱，如 `ext-b`
+    pub id: &'static str,
+    pub name: &'static str,
+    pub kind: BlockKind,
+    pub start: u32,
+    pub end: u32,
+    // 各版本新增字符後，區塊中最後一個已分配的碼位
+    history: &'static [(UnicodeVersion, u32)],
+}
+
+impl Block {
+    /// 整個區塊的範圍，包括未分配的碼位
+    pub fn range(&self) -> RangeInclusive<u32> {
+        self.start..=self.end
+    }
+
+    pub fn contains(&self, codepoint: u32) -> bool {
+        self.range().contains(&codepoint)
+    }
+
+    /// 區塊首次出現的版本
+    pub fn since(&self) -> UnicodeVersion {
+        self.history[0].0
+    }
+
+    /// 指定版本中從區塊起點到最後一個已分配碼位的範圍，該版本尚無此區塊時爲 `None`
+    pub fn assigned(&self, version: UnicodeVersion) -> Option<RangeInclusive<u32>> {
+        self.history
+            .iter()
+            .take_while(|(since, _)| *since <= version)
+            .last()
+            .map(|&(_, last)| self.start..=last)
+    }
+
+    /// 圖表文件的慣用名稱，如 `U20000.pdf`
+    pub fn chart_file_name(&self) -> String {
+        format!("U{:04X}.pdf", self.start)
+    }
+}
+
+impl fmt::Display for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:04X}–{:04X})", self.name, self.start, self.end)
+    }
+}
+
+const fn v(major: u8, minor: u8) -> UnicodeVersion {
+    UnicodeVersion(major, minor)
+}
+
+pub static BLOCKS: &[Block] = &[
+    Block {
+        id: "basic",
+        name: "CJK Unified Ideographs",
+        kind: BlockKind::Unified,
+        start: 0x4E00,
+        end: 0x9FFF,
+        history: &[
+            (v(1, 1), 0x9FA5),
+            (v(4, 1), 0x9FBB),
+            (v(5, 1), 0x9FC3),
+            (v(5, 2), 0x9FCB),
+            (v(6, 1), 0x9FCC),
+            (v(8, 0), 0x9FD5),
+            (v(10, 0), 0x9FEA),
+            (v(11, 0), 0x9FEF),
+            (v(13, 0), 0x9FFC),
+            (v(14, 0), 0x9FFF),
+        ],
+    },
+    Block {
+        id: "ext-a",
+        name: "CJK Unified Ideographs Extension A",
+        kind: BlockKind::Unified,
+        start: 0x3400,
+        end: 0x4DBF,
+        history: &[(v(3, 0), 0x4DB5), (v(13, 0), 0x4DBF)],
+    },
+    Block {
+        id: "ext-b",
+        name: "CJK Unified Ideographs Extension B",
+        kind: BlockKind::Unified,
+        start: 0x20000,
+        end: 0x2A6DF,
+        history: &[(v(3, 1), 0x2A6D6), (v(13, 0), 0x2A6DD), (v(14, 0), 0x2A6DF)],
+    },
+    Block {
+        id: "ext-c",
+        name: "CJK Unified Ideographs Extension C",
+        kind: BlockKind::Unified,
+        start: 0x2A700,
+        end: 0x2B73F,
+        history: &[(v(5, 2), 0x2B734), (v(14, 0), 0x2B738), (v(15, 0), 0x2B739)],
+    },
+    Block {
+        id: "ext-d",
+        name: "CJK Unified Ideographs Extension D",
+        kind: BlockKind::Unified,
+        start: 0x2B740,
+        end: 0x2B81F,
+        history: &[(v(6, 0), 0x2B81D)],
+    },
+    Block {
+        id: "ext-e",
+        name: "CJK Unified Ideographs Extension E",
+        kind: BlockKind::Unified,
+        start: 0x2B820,
+        end: 0x2CEAF,
+        history: &[(v(8, 0), 0x2CEA1)],
+    },
+    Block {
+        id: "ext-f",
+        name: "CJK Unified Ideographs Extension F",
+        kind: BlockKind::Unified,
+        start: 0x2CEB0,
+        end: 0x2EBEF,
+        history: &[(v(10, 0), 0x2EBE0)],
+    },
+    Block {
+        id: "ext-g",
+        name: "CJK Unified Ideographs Extension G",
+        kind: BlockKind::Unified,
+        start: 0x30000,
+        end: 0x3134F,
+        history: &[(v(13, 0), 0x3134A)],
+    },
+    Block {
+        id: "ext-h",
+        name: "CJK Unified Ideographs Extension H",
+        kind: BlockKind::Unified,
+        start: 0x31350,
+        end: 0x323AF,
+        history: &[(v(15, 0), 0x323AF)],
+    },
+    Block {
+        id: "ext-i",
+        name: "CJK Unified Ideographs Extension I",
+        kind: BlockKind::Unified,
+        start: 0x2EBF0,
+        end: 0x2EE5F,
+        history: &[(v(15, 1), 0x2EE5D)],
+    },
+    Block {
+        id: "ext-j",
+        name: "CJK Unified Ideographs Extension J",
+        kind: BlockKind::Unified,
+        start: 0x323B0,
+        end: 0x3347F,
+        history: &[(v(17, 0), 0x33479)],
+    },
+    Block {
+        id: "compat",
+        name: "CJK Compatibility Ideographs",
+        kind: BlockKind::Compatibility,
+        start: 0xF900,
+        end: 0xFAFF,
+        history: &[(v(1, 1), 0xFA2D), (v(3, 2), 0xFA6A), (v(4, 1), 0xFAD9)],
+    },
+    Block {
+        id: "compat-sup",
+        name: "CJK Compatibility Ideographs Supplement",
+        kind: BlockKind::Compatibility,
+        start: 0x2F800,
+        end: 0x2FA1F,
+        history: &[(v(3, 1), 0x2FA1D)],
+    },
+    Block {
+        id: "radicals-sup",
+        name: "CJK Radicals Supplement",
+        kind: BlockKind::Radicals,
+        start: 0x2E80,
+        end: 0x2EFF,
+        history: &[(v(3, 0), 0x2EF3)],
+    },
+    Block {
+        id: "kangxi",
+        name: "Kangxi Radicals",
+        kind: BlockKind::Radicals,
+        start: 0x2F00,
+        end: 0x2FDF,
+        history: &[(v(3, 0), 0x2FD5)],
+    },
+    Block {
+        id: "strokes",
+        name: "CJK Strokes",
+        kind: BlockKind::Strokes,
+        start: 0x31C0,
+        end: 0x31EF,
+        history: &[(v(4, 1), 0x31CF), (v(5, 1), 0x31E3), (v(15, 1), 0x31EF)],
+    },
+    Block {
+        id: "idc",
+        name: "Ideographic Description Characters",
+        kind: BlockKind::Description,
+        start: 0x2FF0,
+        end: 0x2FFF,
+        history: &[(v(3, 0), 0x2FFB), (v(15, 1), 0x2FFF)],
+    },
+];
+
+/// 依名稱查找區塊，不區分大小寫
+pub fn find(id: &str) -> Option<&'static Block> {
+    BLOCKS
+        .iter()
+        .find(|block| block.id.eq_ignore_ascii_case(id.trim()))
+}
+
+/// 碼位所屬的區塊
+pub fn block_of(codepoint: u32) -> Option<&'static Block> {
+    BLOCKS.iter().find(|block| block.contains(codepoint))
+}
+
+/// 指定版本中已存在的區塊
+pub fn blocks_in(version: UnicodeVersion) -> impl Iterator<Item = &'static Block> {
+    BLOCKS.iter().filter(move |block| block.since() <= version)
+}
+
+/// 從圖表文件名推斷區塊，如 `U20000.pdf` 對應擴展 B 區
+pub fn from_file_name<P: AsRef<Path>>(path: P) -> Option<&'static Block> {
+    let stem = path.as_ref().file_stem()?.to_str()?;
+    let hex = stem.strip_prefix('U').or_else(|| stem.strip_prefix('u'))?;
+    let start = u32::from_str_radix(hex, 16).ok()?;
+    BLOCKS.iter().find(|block| block.start == start)
+}
+
+/// 解析 `--block` 參數，接受區塊名稱或圖表文件名
+pub fn parse_block(s: &str) -> Result<&'static Block, String> {
+    find(s).or_else(|| from_file_name(s)).ok_or_else(|| {
+        let ids = BLOCKS.iter().map(|block| block.id).collect::<Vec<_>>();
+        format!("未知的區塊: {}，可選: {}", s, ids.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn block_lookup() {
+        assert_eq!(find("Ext-B").unwrap().start, 0x20000);
+        assert_eq!(block_of(0x2EE5D).unwrap().id, "ext-i");
+        assert_eq!(from_file_name("cjk/U2A700.pdf").unwrap().id, "ext-c");
+        assert!(from_file_name("basic.pdf").is_none());
+
+        let ext_c = find("ext-c").unwrap();
+        assert_eq!(ext_c.assigned(v(14, 0)), Some(0x2A700..=0x2B738));
+        assert_eq!(ext_c.assigned(v(15, 0)), Some(0x2A700..=0x2B739));
+        assert_eq!(ext_c.assigned(v(5, 1)), None);
+
+        assert_eq!("15.1".parse(), Ok(v(15, 1)));
+        assert!(blocks_in(v(15, 0)).all(|block| block.id != "ext-i"));
+    }
+}
@@ -0,0 +1,404 @@
+use std::fmt;
+
+use itertools::Itertools;
+
+use crate::{
+    error::Result,
+    grid::cluster,
+    layout::{font_size, LayoutProfile, SizeRule},
+    page_source::PageSource,
+    parse::{is_chart_glyph, parse_details},
+    report::Report,
+};
+
+/// 默認取樣的圖表頁數
+pub const SAMPLE_PAGES: usize = 3;
+
+// 內置版式 unicode-15 的編碼字號，未能量出的閾值依此按比例縮放
+const REFERENCE_CODE_SIZE: f64 = 9.9998;
+
+/// 一組字號相近的字符
+#[derive(Debug, Clone, Default)]
+pub struct SizeCluster {
+    /// 平均字號
+    pub size: f64,
+    pub min: f64,
+    pub max: f64,
+    pub count: usize,
+    /// 十六進制數字的個數
+    pub hex: usize,
+    /// 字母、數字及連字符的個數
+    pub label: usize,
+    /// 圖表字形的個數
+    pub glyph: usize,
+}
+
+impl SizeCluster {
+    fn ratio(&self, n: usize) -> f64 {
+        n as f64 / self.count as f64
+    }
+
+    fn is_glyph(&self) -> bool {
+        self.ratio(self.glyph) >= 0.5
+    }
+
+    fn rule(&self) -> SizeRule {
+        let spread = (self.max - self.size).max(self.size - self.min);
+        SizeRule::new(self.size, spread + 0.01)
+    }
+}
+
+/// 從取樣頁面推斷出的版式
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    pub profile: LayoutProfile,
+    /// 取樣的頁碼
+    pub pages: Vec<usize>,
+    pub clusters: Vec<SizeCluster>,
+}
+
+impl fmt::Display for Calibration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let profile = &self.profile;
+        writeln!(
+            f,
+            "自動校準版式，取樣頁面: {}",
+            self.pages.iter().join(", ")
+        )?;
+        for cluster in &self.clusters {
+            let role = if profile.code.size == cluster.size {
+                "編碼"
+            } else if profile.source.size == cluster.size {
+                "字源"
+            } else if profile.is_glyph(cluster.size) {
+                "字形"
+            } else {
+                "其他"
+            };
+            writeln!(
+                f,
+                "  字號 {:.4} ({:.4}–{:.4})\t{} 個\t{}",
+                cluster.size, cluster.min, cluster.max, cluster.count, role
+            )?;
+        }
+        writeln!(
+            f,
+            "  編碼 {:.4}±{:.4}，字源 {:.4}±{:.4}，字形字號 > {:.2}",
+            profile.code.size,
+            profile.code.tolerance,
+            profile.source.size,
+            profile.source.tolerance,
+            profile.min_glyph_size
+        )?;
+        write!(
+            f,
+            "  編碼間距 {:.2}，字源間距 {:.2}，同行容差 {:.2}，行距 {:.2}，同列容差 {:.2}",
+            profile.code_gap,
+            profile.source_gap,
+            profile.row_tolerance,
+            profile.row_gap,
+            profile.column_tolerance
+        )
+    }
+}
+
+/// 分析首頁之後的前 `sample_pages` 頁：按字號聚類找出編碼、字源與字形，再量出字距與行距
+pub fn calibrate(doc: &dyn PageSource, sample_pages: usize) -> Result<Calibration> {
+    let pages = (1..doc.page_count()).take(sample_pages).collect_vec();
+    let mut items = vec![];
+    for &page_idx in &pages {
+        let page_svg = doc
+            .page_svg(page_idx)
+            .map_err(|err| err.at_page(page_idx))?;
+        // 空白頁沒有 <g>，此時視爲無文字
+        let detail_list = parse_details(&page_svg).unwrap_or_default();
+        items.extend(
+            detail_list
+                .into_iter()
+                .filter(|(ch, _, _)| !ch.is_empty())
+                .map(|(ch, _, matrix)| (ch, matrix)),
+        );
+    }
+    if items.is_empty() {
+        return Err("取樣頁面中沒有文字".into());
+    }
+
+    let clusters = cluster_sizes(&items);
+    let glyph_min = clusters
+        .iter()
+        .filter(|cluster| cluster.is_glyph())
+        .map(|cluster| cluster.min)
+        .min_by(f64::total_cmp)
+        .ok_or("取樣頁面中沒有字形")?;
+    let labels = clusters
+        .iter()
+        .filter(|cluster| cluster.max < glyph_min && cluster.ratio(cluster.label) >= 0.8)
+        .collect_vec();
+    let code = labels
+        .iter()
+        .filter(|cluster| cluster.ratio(cluster.hex) >= 0.9)
+        .max_by(|a, b| {
+            a.ratio(a.hex)
+                .total_cmp(&b.ratio(b.hex))
+                .then(a.count.cmp(&b.count))
+        })
+        .ok_or("找不到編碼標籤")?;
+    let source = labels
+        .iter()
+        .filter(|cluster| cluster.size != code.size)
+        .max_by_key(|cluster| cluster.label)
+        .ok_or("找不到字源標籤")?;
+    // 取字形與其下最大一組非字形字號的中點
+    let below_glyph = clusters
+        .iter()
+        .filter(|cluster| cluster.max < glyph_min)
+        .map(|cluster| cluster.max)
+        .fold(0.0, f64::max);
+
+    let scale = code.size / REFERENCE_CODE_SIZE;
+    let reference = LayoutProfile::unicode15();
+    let code_rule = code.rule();
+    let source_rule = source.rule();
+    let positions = |rule: SizeRule| {
+        items
+            .iter()
+            .filter(|(_, matrix)| rule.matches(font_size(matrix)))
+            .map(|(_, matrix)| (matrix[4], matrix[5]))
+            .collect_vec()
+    };
+    let code_positions = positions(code_rule);
+    let source_positions = positions(source_rule);
+
+    let code_gap = label_gap(&code_positions).unwrap_or(reference.code_gap * scale);
+    let row_pitch = row_pitch(&source_positions);
+    let column_pitch = column_pitch(&label_spans(&code_positions, code_gap));
+    let profile = LayoutProfile {
+        name: "auto".to_string(),
+        code: code_rule,
+        source: source_rule,
+        min_glyph_size: (below_glyph + glyph_min) / 2.0,
+        code_gap,
+        source_gap: label_gap(&source_positions).unwrap_or(reference.source_gap * scale),
+        row_tolerance: row_pitch.map_or(reference.row_tolerance * scale, |pitch| pitch / 4.0),
+        row_gap: row_pitch.map_or(reference.row_gap * scale, |pitch| pitch / 2.0),
+        column_tolerance: column_pitch
+            .map_or(reference.column_tolerance * scale, |pitch| pitch / 4.0),
+    };
+
+    Ok(Calibration {
+        profile,
+        pages,
+        clusters,
+    })
+}
+
+/// 已指定版式時直接使用，否則自動校準，校準失敗則改用默認版式並記入 `report`
+pub fn layout_or_calibrate(
+    doc: &dyn PageSource,
+    layout: Option<&LayoutProfile>,
+    report: &mut Report,
+) -> LayoutProfile {
+    if let Some(layout) = layout {
+        return layout.clone();
+    }
+
+    match calibrate(doc, SAMPLE_PAGES) {
+        Ok(calibration) => {
+            println!("{}", calibration);
+            calibration.profile
+        }
+        Err(err) => {
+            let layout = LayoutProfile::default();
+            report.warn(
+                err.page(),
+                vec![],
+                format!("無法自動校準版式: {}，改用 {}", err, layout),
+            );
+            layout
+        }
+    }
+}
+
+// 字號排序後，相鄰字號之差超過 max(0.3, 3%) 即分爲兩組
+fn cluster_sizes(items: &[(String, Vec<f64>)]) -> Vec<SizeCluster> {
+    let mut clusters: Vec<SizeCluster> = vec![];
+    let sorted = items
+        .iter()
+        .map(|(ch, matrix)| (font_size(matrix), ch))
+        .sorted_by(|a, b| a.0.total_cmp(&b.0));
+
+    for (size, ch) in sorted {
+        let cluster = match clusters.last_mut() {
+            Some(cluster) if size - cluster.max <= (cluster.max * 0.03).max(0.3) => cluster,
+            _ => {
+                clusters.push(SizeCluster {
+                    min: size,
+                    ..Default::default()
+                });
+                clusters.last_mut().unwrap()
+            }
+        };
+
+        cluster.max = size;
+        cluster.size += size;
+        cluster.count += 1;
+        if ch
+            .chars()
+            .all(|c| c.is_ascii_hexdigit() && !c.is_ascii_lowercase())
+        {
+            cluster.hex += 1;
+        }
+        if ch.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            cluster.label += 1;
+        }
+        if is_chart_glyph(ch, &(0..=0)) {
+            cluster.glyph += 1;
+        }
+    }
+
+    for cluster in &mut clusters {
+        cluster.size /= cluster.count as f64;
+    }
+
+    clusters
+}
+
+/// 同一行內相鄰字符的橫向間距由小到大分爲字內、標籤之間與格子之間幾類，
+/// 取字內之後第一個跳躍處；只有一類時取最大字距的 1.75 倍
+fn label_gap(positions: &[(f64, f64)]) -> Option<f64> {
+    let diffs = positions
+        .iter()
+        .sorted_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)))
+        .group_by(|(_, y)| (y * 100.0).round() as i64)
+        .into_iter()
+        .flat_map(|(_, row)| {
+            row.tuple_windows()
+                .map(|(a, b)| b.0 - a.0)
+                .filter(|diff| *diff > 0.01)
+                .collect_vec()
+        })
+        .sorted_by(f64::total_cmp)
+        .collect_vec();
+
+    let max = *diffs.last()?;
+    let jump = diffs
+        .iter()
+        .tuple_windows()
+        .find(|(small, large)| **large / **small >= 2.0);
+    match jump {
+        Some((small, large)) => Some((small + large) / 2.0),
+        None => Some(max * 1.75),
+    }
+}
+
+/// 同一行內按 `gap` 將字符連成標籤，返回各標籤首尾字符的橫坐標
+fn label_spans(positions: &[(f64, f64)], gap: f64) -> Vec<(f64, f64)> {
+    let mut spans: Vec<(f64, f64)> = vec![];
+    for (_, row) in &positions
+        .iter()
+        .sorted_by(|a, b| a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0)))
+        .group_by(|(_, y)| (y * 100.0).round() as i64)
+    {
+        let row_start = spans.len();
+        for &(x, _) in row {
+            let in_row = spans.len() > row_start;
+            match spans.last_mut() {
+                Some(span) if in_row && x - span.1 <= gap => span.1 = x,
+                _ => spans.push((x, x)),
+            }
+        }
+    }
+
+    spans
+}
+
+/// 相鄰兩列編碼的最小橫向距離：標籤起點之差超過最寬的標籤即屬不同列，否則只是同列的抖動
+fn column_pitch(spans: &[(f64, f64)]) -> Option<f64> {
+    let width = spans
+        .iter()
+        .map(|(start, end)| end - start)
+        .fold(0.0, f64::max);
+    spans
+        .iter()
+        .map(|&(start, _)| start)
+        .sorted_by(f64::total_cmp)
+        .tuple_windows()
+        .map(|(a, b)| b - a)
+        .filter(|diff| *diff > width)
+        .min_by(f64::total_cmp)
+}
+
+/// 相鄰兩行字源的最小縱向距離
+fn row_pitch(positions: &[(f64, f64)]) -> Option<f64> {
+    cluster(positions.iter().map(|&(_, y)| y), 1.0)
+        .iter()
+        .tuple_windows()
+        .map(|(a, b)| b.start - a.start)
+        .min_by(f64::total_cmp)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_source::MemoryPageSource;
+
+    #[test]
+    fn calibrate_shifted_sizes() {
+        // 編碼與字源的字號分別由 9.9998、6 改爲 10、6.5；編碼分爲相距 150 的兩列，同列略有偏差
+        let mut uses = vec![("&#x4e00;", 22.0, 120.0, 130.0)];
+        for (text, x, y) in [
+            ("4E00", 50.0, 100.0),
+            ("4E01", 200.0, 100.0),
+            ("4E02", 51.0, 160.0),
+        ] {
+            for (i, ch) in ["4", "E", "0", &text[3..]].into_iter().enumerate() {
+                uses.push((ch, 10.0, x + 6.0 * i as f64, y));
+            }
+        }
+        for (i, ch) in ["G", "0", "-", "5", "2", "3", "B"].into_iter().enumerate() {
+            uses.push((ch, 6.5, 118.0 + 3.5 * i as f64, 145.0));
+        }
+        let uses = uses
+            .into_iter()
+            .map(|(text, size, x, y)| {
+                format!(
+                    r##"<use data-text="{}" xlink:href="#f" transform="matrix({},0,0,-{},{},{})"/>"##,
+                    text, size, size, x, y
+                )
+            })
+            .collect::<String>();
+        let page = format!(
+            r#"<svg><defs><path id="f" d="M0 0L1 1"/></defs><g>{}</g></svg>"#,
+            uses
+        );
+        let doc = MemoryPageSource::new(["<svg><defs/></svg>".to_string(), page]);
+
+        let calibration = calibrate(&doc, SAMPLE_PAGES).unwrap();
+        let profile = &calibration.profile;
+        assert_eq!(calibration.pages, vec![1]);
+        assert!(profile.is_code(10.0) && !profile.is_code(9.0));
+        assert!(profile.is_source(6.5) && !profile.is_source(6.0));
+        assert!(profile.is_glyph(22.0) && !profile.is_glyph(10.0));
+        assert!(profile.code_gap > 6.0 && profile.source_gap > 3.5);
+        assert_eq!(profile.column_tolerance, 149.0 / 4.0);
+    }
+
+    #[test]
+    fn calibration_fallback() {
+        let doc = MemoryPageSource::new(["<svg><defs/></svg>", "<svg><defs/></svg>"]);
+        let mut report = Report::default();
+        let layout = layout_or_calibrate(&doc, None, &mut report);
+        assert_eq!(layout, LayoutProfile::default());
+        assert_eq!(report.len(), 1);
+        assert!(report.problems[0].reason.starts_with("無法自動校準版式"));
+
+        // 已指定版式時不校準
+        let mut report = Report::default();
+        let legacy = LayoutProfile::legacy();
+        assert_eq!(
+            layout_or_calibrate(&doc, Some(&legacy), &mut report),
+            legacy
+        );
+        assert!(report.is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+use std::ops::RangeBounds;
+
+use itertools::Itertools;
+use serde::{ser::SerializeStruct, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    grid::PageGrid,
+    layout::LayoutProfile,
+    parse::*,
+    source::{DictionaryRef, SourceRef},
+};
+
+/// 圖表中的一個字源及其字形
+#[derive(Debug, Clone)]
+pub struct EntryGlyph {
+    pub source: SourceRef,
+    // 以 em 爲單位的字形路徑
+    pub path: String,
+}
+
+// 附上字源所屬的地區及其在國家標準或字典中的位置
+impl Serialize for EntryGlyph {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("EntryGlyph", 5)?;
+        state.serialize_field("source", &self.source)?;
+        state.serialize_field("region", &self.source.region)?;
+        state.serialize_field("standard", &self.source.standard_code())?;
+        state.serialize_field("dictionary", &self.source.dictionary_ref())?;
+        state.serialize_field("path", &self.path)?;
+        state.end()
+    }
+}
+
+/// 圖表中的一個碼位
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartEntry {
+    #[serde(serialize_with = "serialize_hex")]
+    pub codepoint: u32,
+    pub character: char,
+    pub page: usize,
+    pub glyphs: Vec<EntryGlyph>,
+}
+
+impl ChartEntry {
+    pub fn hex(&self) -> String {
+        format!("{:X}", self.codepoint)
+    }
+
+    /// 各字源所引用的字典位置中最靠前的一個
+    pub fn dictionary_ref(&self) -> Option<DictionaryRef> {
+        self.glyphs
+            .iter()
+            .filter_map(|glyph| glyph.source.dictionary_ref())
+            .min()
+    }
+}
+
+fn serialize_hex<S: serde::Serializer>(codepoint: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:X}", codepoint))
+}
+
+#[derive(Debug, Clone)]
+pub struct ChartPage {
+    pub page_idx: usize,
+    pub entries: Vec<ChartEntry>,
+    /// 未能配對而被略去的字形與字源
+    pub unpaired: Vec<Unpaired>,
+    /// 由編碼位置得出的網格
+    pub grid: PageGrid,
+}
+
+/// 頁面上未能配對的字形或字源，如註腳中的字形或缺少標籤的字形，以及字源標籤無法識別的字形
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unpaired {
+    Glyph {
+        character: char,
+        x: f64,
+        y: f64,
+    },
+    Source {
+        source: String,
+        x: f64,
+        y: f64,
+    },
+    Unrecognized {
+        character: char,
+        source: String,
+        reason: String,
+    },
+}
+
+impl Unpaired {
+    /// 字形對應的碼位，字源則無從得知
+    pub fn codepoints(&self) -> Vec<u32> {
+        match self {
+            Unpaired::Glyph { character, .. } | Unpaired::Unrecognized { character, .. } => {
+                vec![u32::from(*character)]
+            }
+            Unpaired::Source { .. } => vec![],
+        }
+    }
+
+    pub fn to_error(&self) -> Error {
+        match self {
+            Unpaired::Glyph { character, x, y } => {
+                Error::Unpaired(format!("字形 {} ({:.1}, {:.1})", character, x, y))
+            }
+            Unpaired::Source { source, x, y } => {
+                Error::Unpaired(format!("字源 {} ({:.1}, {:.1})", source, x, y))
+            }
+            Unpaired::Unrecognized {
+                character, reason, ..
+            } => Error::Invalid(format!("已略去 {} 的字形: {}", character, reason)),
+        }
+    }
+}
+
+impl ChartPage {
+    /// 只有碼位而沒有字形的頁面，用於寬鬆模式下解析失敗的頁面
+    pub fn without_glyphs(page_idx: usize, codes: Vec<(u32, char)>) -> Self {
+        let entries = codes
+            .into_iter()
+            .map(|(codepoint, character)| ChartEntry {
+                codepoint,
+                character,
+                page: page_idx,
+                glyphs: vec![],
+            })
+            .collect();
+
+        Self {
+            page_idx,
+            entries,
+            unpaired: vec![],
+            grid: PageGrid::default(),
+        }
+    }
+}
+
+/// 單頁中各類元素的數量，用於檢查
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PageStats {
+    pub codes: usize,
+    pub sources: usize,
+    pub graphs: usize,
+    /// 網格的列數與行數
+    pub columns: usize,
+    pub rows: usize,
+    pub first_codepoint: Option<u32>,
+    pub last_codepoint: Option<u32>,
+}
+
+pub fn page_stats<R: RangeBounds<u32>>(
+    page_svg: &str,
+    codepoint_range: &R,
+    layout: &LayoutProfile,
+) -> Result<PageStats> {
+    let font_dic = parse_font_drawn(page_svg)?;
+    let detail_list = parse_details(page_svg)?;
+
+    let source_vec = ChartSource::new(&detail_list, layout);
+    let graph_vec = ChartGraph::new(&detail_list, &font_dic, codepoint_range, layout)?;
+    let code_vec = ChartCode::new(&detail_list, layout)?;
+    let grid = PageGrid::from_codes(&code_vec, layout);
+
+    Ok(PageStats {
+        codes: code_vec.len(),
+        sources: source_vec.len(),
+        graphs: graph_vec.len(),
+        columns: grid.columns.len(),
+        rows: grid.rows.len(),
+        first_codepoint: code_vec.iter().map(|code| code.codepoint).min(),
+        last_codepoint: code_vec.iter().map(|code| code.codepoint).max(),
+    })
+}
+
+/// 頁面上所有的編碼，解析失敗時爲空，用於寬鬆模式下記錄受影響的碼位
+pub fn page_codepoints(page_svg: &str, layout: &LayoutProfile) -> Vec<(u32, char)> {
+    let Ok(detail_list) = parse_details(page_svg) else {
+        return vec![];
+    };
+    let mut code_vec = ChartCode::new(&detail_list, layout).unwrap_or_default();
+    PageGrid::from_codes(&code_vec, layout).sort_by_columns(&mut code_vec);
+
+    code_vec
+        .into_iter()
+        .map(|code| (code.codepoint, code.hanzi))
+        .collect()
+}
+
+pub fn parse_page<R: RangeBounds<u32>>(
+    page_svg: &str,
+    page_idx: usize,
+    codepoint_range: &R,
+    layout: &LayoutProfile,
+) -> Result<ChartPage> {
+    parse_page_inner(page_svg, page_idx, codepoint_range, layout)
+        .map_err(|err| err.at_page(page_idx))
+}
+
+fn parse_page_inner<R: RangeBounds<u32>>(
+    page_svg: &str,
+    page_idx: usize,
+    codepoint_range: &R,
+    layout: &LayoutProfile,
+) -> Result<ChartPage> {
+    // 初步解析
+    let font_dic = parse_font_drawn(page_svg)?;
+    let detail_list = parse_details(page_svg)?; // character, font-name, matrix
+
+    // 結構化
+    let mut source_vec = ChartSource::new(&detail_list, layout);
+    let mut graph_vec = ChartGraph::new(&detail_list, &font_dic, codepoint_range, layout)?;
+    let mut code_vec = ChartCode::new(&detail_list, layout)?; // 整頁的code_vec
+
+    // 依編碼的位置劃分網格，字源與字形按行、列排序
+    let grid = PageGrid::from_codes(&code_vec, layout);
+    grid.sort_by_rows(&mut source_vec);
+    grid.sort_by_rows(&mut graph_vec);
+
+    // 依位置配對字形與字源
+    let pairing = pair_graph_source(&graph_vec, &source_vec, layout);
+    let mut unpaired = pairing
+        .graphs
+        .iter()
+        .map(|graph| Unpaired::Glyph {
+            character: graph.ch,
+            x: graph.x,
+            y: graph.y,
+        })
+        .chain(pairing.sources.iter().map(|source| Unpaired::Source {
+            source: source.source.clone(),
+            x: source.x_min,
+            y: source.y,
+        }))
+        .collect_vec();
+
+    // 按列、行的順序排列條目
+    grid.sort_by_columns(&mut code_vec);
+    let cells = grid.index_codes(&code_vec);
+    let mut entries = code_vec
+        .iter()
+        .map(|code| ChartEntry {
+            codepoint: code.codepoint,
+            character: code.hanzi,
+            page: page_idx,
+            glyphs: vec![],
+        })
+        .collect_vec();
+
+    // 每對 (字形, 字源) 歸入字源所在格子的編碼，字源標籤無法識別時只略去該字形
+    for (graph, source) in pairing.pairs {
+        match grid.cell_of(source).and_then(|cell| cells.get(&cell)) {
+            Some(&idx) => match source.source.parse() {
+                Ok(source_ref) => entries[idx].glyphs.push(EntryGlyph {
+                    source: source_ref,
+                    path: graph.drawn.to_string(),
+                }),
+                Err(reason) => unpaired.push(Unpaired::Unrecognized {
+                    character: entries[idx].character,
+                    source: source.source.clone(),
+                    reason,
+                }),
+            },
+            None => unpaired.push(Unpaired::Glyph {
+                character: graph.ch,
+                x: graph.x,
+                y: graph.y,
+            }),
+        }
+    }
+    // 同一碼位的字源按地區排列
+    for entry in &mut entries {
+        entry.glyphs.sort_by_key(|glyph| glyph.source.region);
+    }
+
+    Ok(ChartPage {
+        page_idx,
+        entries,
+        unpaired,
+        grid,
+    })
+}
@@ -0,0 +1,328 @@
+use std::ops::RangeInclusive;
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    blocks::{block_of, Block, UnicodeVersion, BLOCKS},
+    chart::page_stats,
+    error::Result,
+    layout::LayoutProfile,
+    page_source::PageSource,
+    parse::parse_details,
+    re,
+};
+
+/// 從圖表本身識別出的區塊與碼位範圍
+#[derive(Debug, Clone, Default)]
+pub struct Detection {
+    /// 首頁標題或首個編碼所屬的區塊
+    pub block: Option<&'static Block>,
+    /// 首頁上的 `Range: 4E00–9FFF`
+    pub cover_range: Option<RangeInclusive<u32>>,
+    /// 首個圖表頁的第一個編碼至最後一頁的最後一個編碼
+    pub code_range: Option<RangeInclusive<u32>>,
+}
+
+impl Detection {
+    /// 用於篩選字形的範圍
+    pub fn range(&self) -> Option<RangeInclusive<u32>> {
+        self.cover_range
+            .clone()
+            .or_else(|| self.block.map(Block::range))
+            .or_else(|| self.code_range.clone())
+    }
+
+    /// 檢查用戶指定的範圍是否與圖表相符，兩者不相交時報錯
+    pub fn check(&self, requested: &RangeInclusive<u32>) -> Result<(), String> {
+        let Some(range) = self.range() else {
+            return Ok(());
+        };
+        if requested.start() <= range.end() && range.start() <= requested.end() {
+            return Ok(());
+        }
+
+        let chart = match self.block {
+            Some(block) => format!("{} ({:04X}–{:04X})", block.name, range.start(), range.end()),
+            None => format!("{:04X}–{:04X}", range.start(), range.end()),
+        };
+        Err(format!(
+            "指定的碼位範圍 {:04X}–{:04X} 與圖表不符，圖表爲 {}",
+            requested.start(),
+            requested.end(),
+            chart
+        ))
+    }
+}
+
+/// 將頁面上的文字按行拼接，行內按橫坐標排序
+pub fn page_lines(page_svg: &str) -> Vec<String> {
+    // 空白頁沒有 <g>，此時視爲無文字
+    parse_details(page_svg)
+        .unwrap_or_default()
+        .into_iter()
+        .sorted_by(|(_, _, a), (_, _, b)| a[5].total_cmp(&b[5]).then(a[4].total_cmp(&b[4])))
+        .group_by(|(_, _, mat)| (mat[5] * 2.0).round() as i64)
+        .into_iter()
+        .map(|(_, line)| line.map(|(ch, _, _)| ch).collect::<String>())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// 從首頁的文字中識別區塊名稱與 `Range:` 一行，忽略空白
+fn detect_cover(page_svg: &str) -> (Option<&'static Block>, Option<RangeInclusive<u32>>) {
+    let text = page_lines(page_svg).join("\n");
+    let squashed = text
+        .chars()
+        .filter(|ch| !ch.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+
+    // 取最長的匹配，以免擴展區被識別爲基本區
+    let block = BLOCKS
+        .iter()
+        .filter(|block| {
+            let name = block.name.replace(' ', "").to_ascii_lowercase();
+            squashed.contains(&name)
+        })
+        .max_by_key(|block| block.name.len());
+
+    let range_regex = re!(r"(?i)Range:?\s*([0-9A-F]{4,6})\s*[–-]\s*([0-9A-F]{4,6})");
+    let range = range_regex.captures(&text).and_then(|c| {
+        let start = u32::from_str_radix(&c[1], 16).ok()?;
+        let end = u32::from_str_radix(&c[2], 16).ok()?;
+        (start <= end).then_some(start..=end)
+    });
+
+    (block, range)
+}
+
+/// 依次讀取首頁、首個圖表頁與最後一頁
+pub fn detect_chart(
+    doc: &dyn PageSource,
+    cover: &ChartMetadata,
+    layout: &LayoutProfile,
+) -> Result<Detection> {
+    let page_count = doc.page_count();
+    let mut detection = Detection {
+        cover_range: cover.codepoints.clone(),
+        ..Default::default()
+    };
+
+    if page_count > 1 {
+        let code_stats = |page_idx| -> Result<_> {
+            let page_svg = doc.page_svg(page_idx)?;
+            if page_lines(&page_svg).is_empty() {
+                return Ok(Default::default());
+            }
+            page_stats(&page_svg, &(0..=0), layout).map_err(|err| err.at_page(page_idx))
+        };
+        let first = code_stats(1)?.first_codepoint;
+        let last = code_stats(page_count - 1)?.last_codepoint;
+        detection.code_range = match (first, last) {
+            (Some(first), Some(last)) if first <= last => Some(first..=last),
+            (Some(first), _) => Some(first..=first),
+            _ => None,
+        };
+    }
+
+    detection.block = cover.block.or_else(|| {
+        let range = detection.code_range.as_ref()?;
+        block_of(*range.start())
+    });
+
+    Ok(detection)
+}
+
+/// 圖表首頁記載的信息，會寫入各種輸出
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ChartMetadata {
+    /// 首頁標題或 `Range:` 起點所屬的區塊，輸出其名稱
+    #[serde(serialize_with = "serialize_block")]
+    pub block: Option<&'static Block>,
+    #[serde(serialize_with = "serialize_range")]
+    pub codepoints: Option<RangeInclusive<u32>>,
+    pub unicode_version: Option<UnicodeVersion>,
+    pub chart_date: Option<String>,
+}
+
+impl ChartMetadata {
+    /// 碼位範圍，如 `4E00-9FFF`
+    pub fn range_hex(&self) -> Option<String> {
+        let range = self.codepoints.as_ref()?;
+        Some(format!("{:04X}-{:04X}", range.start(), range.end()))
+    }
+
+    /// 以 (名稱, 值) 形式列出已知的項目
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        [
+            ("block", self.block.map(|block| block.name.to_string())),
+            ("codepoints", self.range_hex()),
+            (
+                "unicode_version",
+                self.unicode_version.map(|v| v.to_string()),
+            ),
+            ("chart_date", self.chart_date.clone()),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect()
+    }
+}
+
+fn serialize_block<S: serde::Serializer>(
+    block: &Option<&'static Block>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match block {
+        Some(block) => serializer.serialize_str(block.name),
+        None => serializer.serialize_none(),
+    }
+}
+
+fn serialize_range<S: serde::Serializer>(
+    range: &Option<RangeInclusive<u32>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match range {
+        Some(range) => {
+            serializer.collect_str(&format_args!("{:04X}-{:04X}", range.start(), range.end()))
+        }
+        None => serializer.serialize_none(),
+    }
+}
+
+/// 從首頁的文字中讀取區塊名稱、碼位範圍、Unicode 版本與圖表日期
+pub fn parse_cover(page_svg: &str) -> ChartMetadata {
+    let (block, codepoints) = detect_cover(page_svg);
+    let text = page_lines(page_svg).join("\n");
+
+    let version_regex = re!(r"(?i)Unicode\s*(?:Standard)?,?\s*(?:Version)?\s*(\d+)\.(\d+)");
+    let unicode_version = version_regex
+        .captures(&text)
+        .and_then(|c| Some(UnicodeVersion(c[1].parse().ok()?, c[2].parse().ok()?)));
+
+    let date_regex = re!(r"\b(\d{4}-\d{2}-\d{2})\b");
+    let chart_date = date_regex.captures(&text).map(|c| c[1].to_string());
+
+    ChartMetadata {
+        block: block.or_else(|| block_of(*codepoints.as_ref()?.start())),
+        codepoints,
+        unicode_version,
+        chart_date,
+    }
+}
+
+/// 解析首頁，結果供 [`detect_chart`] 與輸出共用；首頁上沒有日期時，取 PDF 的創建日期
+pub fn chart_metadata(doc: &dyn PageSource) -> Result<ChartMetadata> {
+    if doc.page_count() == 0 {
+        return Ok(ChartMetadata::default());
+    }
+
+    let mut metadata = parse_cover(&doc.page_svg(0)?);
+    if metadata.chart_date.is_none() {
+        metadata.chart_date = doc.metadata().creation_date.as_deref().and_then(pdf_date);
+    }
+
+    Ok(metadata)
+}
+
+// PDF 日期，如 `D:20220721123000-07'00'`，只保留日期部分
+fn pdf_date(date: &str) -> Option<String> {
+    let digits = date.strip_prefix("D:").unwrap_or(date);
+    let ymd = digits.get(..8)?;
+    if !ymd.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    Some(format!("{}-{}-{}", &ymd[..4], &ymd[4..6], &ymd[6..]))
+}
+
+/// 用戶指定了範圍時檢查其與圖表是否相符，否則使用從圖表識別的範圍
+pub fn resolve_codepoints(
+    detection: &Detection,
+    requested: Option<RangeInclusive<u32>>,
+) -> Result<RangeInclusive<u32>> {
+    match requested {
+        Some(requested) => {
+            detection.check(&requested)?;
+            Ok(requested)
+        }
+        None => Ok(detection
+            .range()
+            .ok_or("無法從圖表中識別碼位範圍，請指定 --codepoints 或 --block")?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_source::MemoryPageSource;
+
+    const COVER_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="612" height="792" viewBox="0 0 612 792">
+<defs>
+<path id="font_1_2" d="M0 0L.5 0"/>
+</defs>
+<g>
+<use data-text="C" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,50,100)"/>
+<use data-text="J" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,64,100)"/>
+<use data-text="K" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,76,100)"/>
+<use data-text="E" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,100,100)"/>
+<use data-text="x" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,112,100)"/>
+<use data-text="t" xlink:href="#font_1_2" transform="matrix(20,0,0,-20,122,100)"/>
+<use data-text="R" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,50,130)"/>
+<use data-text="a" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,57,130)"/>
+<use data-text="n" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,63,130)"/>
+<use data-text="g" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,69,130)"/>
+<use data-text="e" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,75,130)"/>
+<use data-text=":" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,81,130)"/>
+<use data-text="4" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,90,130)"/>
+<use data-text="E" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,96,130)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,102,130)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,108,130)"/>
+<use data-text="&#x2013;" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,114,130)"/>
+<use data-text="9" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,120,130)"/>
+<use data-text="F" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,126,130)"/>
+<use data-text="F" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,132,130)"/>
+<use data-text="F" xlink:href="#font_1_2" transform="matrix(10,0,0,-10,138,130)"/>
+<use data-text="U" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,50,160)"/>
+<use data-text="n" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,56,160)"/>
+<use data-text="i" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,61,160)"/>
+<use data-text="c" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,64,160)"/>
+<use data-text="o" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,69,160)"/>
+<use data-text="d" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,74,160)"/>
+<use data-text="e" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,79,160)"/>
+<use data-text=" " xlink:href="#font_1_2" transform="matrix(8,0,0,-8,84,160)"/>
+<use data-text="1" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,88,160)"/>
+<use data-text="5" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,93,160)"/>
+<use data-text="." xlink:href="#font_1_2" transform="matrix(8,0,0,-8,98,160)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(8,0,0,-8,101,160)"/>
+</g>
+</svg>
+"##;
+
+    #[test]
+    fn detect_cover_range() {
+        let (_, range) = detect_cover(COVER_SVG);
+        assert_eq!(range, Some(0x4E00..=0x9FFF));
+
+        let doc = MemoryPageSource::new([COVER_SVG]);
+        let metadata = chart_metadata(&doc).unwrap();
+        assert_eq!(metadata, parse_cover(COVER_SVG));
+        let detection = detect_chart(&doc, &metadata, &LayoutProfile::default()).unwrap();
+        assert_eq!(detection.block.unwrap().id, "basic");
+        assert!(detection.check(&(0x4E00..=0x4EFF)).is_ok());
+        assert!(detection.check(&(0x20000..=0x2A6DF)).is_err());
+
+        assert_eq!(
+            metadata.block.map(|block| block.name),
+            Some("CJK Unified Ideographs")
+        );
+        assert_eq!(metadata.range_hex().as_deref(), Some("4E00-9FFF"));
+        assert_eq!(metadata.unicode_version, Some(UnicodeVersion(15, 0)));
+        assert_eq!(
+            pdf_date("D:20220721123000-07'00'").as_deref(),
+            Some("2022-07-21")
+        );
+    }
+}
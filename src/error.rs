@@ -0,0 +1,118 @@
+use std::{io, path::PathBuf};
+
+/// 本庫各函數返回的錯誤
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("頁面 SVG 中缺少 <{0}> 元素")]
+    MissingElement(&'static str),
+
+    #[error("頁面 SVG 格式有誤: {0}")]
+    Svg(String),
+
+    #[error("無效的變換矩陣: {0}")]
+    MalformedMatrix(String),
+
+    #[error("未定義的字形引用: #{0}")]
+    UnknownFont(String),
+
+    #[error("無效的碼位: {0}")]
+    InvalidCodepoint(String),
+
+    #[error("未能配對的{0}")]
+    Unpaired(String),
+
+    #[error("渲染字形失敗: {0}")]
+    Render(String),
+
+    #[error("無法解析字形路徑: {0}")]
+    Outline(String),
+
+    #[error("寫入 xlsx 失敗: {0}")]
+    Xlsx(#[from] rust_xlsxwriter::XlsxError),
+
+    #[error("第 {page} 頁: {source}")]
+    Page { page: usize, source: Box<Error> },
+
+    #[error("讀取 PDF 失敗: {0}")]
+    Pdf(String),
+
+    #[error("{}: {source}", path.display())]
+    File { path: PathBuf, source: io::Error },
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("寫入 JSON 失敗: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("寫入 CSV 失敗: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("壓縮 xlsx 失敗: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("TOML 格式有誤: {0}")]
+    Toml(#[from] toml::de::Error),
+
+    #[cfg(feature = "pymupdf")]
+    #[error("PyMuPDF 錯誤: {0}")]
+    Python(#[from] pyo3::PyErr),
+
+    /// 參數、範圍檢查等其他錯誤
+    #[error("{0}")]
+    Invalid(String),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    /// 附上出錯的頁碼，已帶頁碼的錯誤保持不變
+    pub fn at_page(self, page: usize) -> Self {
+        match self {
+            Error::Page { .. } => self,
+            err => Error::Page {
+                page,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// 出錯的頁碼
+    pub fn page(&self) -> Option<usize> {
+        match self {
+            Error::Page { page, .. } => Some(*page),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn file(path: impl Into<PathBuf>, source: io::Error) -> Self {
+        Error::File {
+            path: path.into(),
+            source,
+        }
+    }
+}
+
+impl From<lopdf::Error> for Error {
+    fn from(err: lopdf::Error) -> Self {
+        Error::Pdf(err.to_string())
+    }
+}
+
+impl From<quick_xml::Error> for Error {
+    fn from(err: quick_xml::Error) -> Self {
+        Error::Svg(err.to_string())
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Invalid(message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::Invalid(message.to_string())
+    }
+}
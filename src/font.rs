@@ -0,0 +1,839 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::{Error, Result},
+    outline::{Outline, Point, Segment},
+};
+
+/// 字體的 em 大小
+pub const UNITS_PER_EM: u16 = 1000;
+
+// post 表 2.0 版中自定義字形名的序號爲 258–32767，其後保留
+const POST_MAX_NAMES: usize = 32767 - 257;
+
+// 三次曲線轉爲二次曲線時允許的誤差（字體單位）
+const CUBIC_TOLERANCE: f32 = 0.5;
+
+/// 字體中的一個字形
+#[derive(Debug, Clone)]
+pub struct FontGlyph {
+    /// 字形名，如 `G0_523B`，須爲 ASCII
+    pub name: String,
+    /// 經 cmap 映射到此字形的碼位，同一碼位只映射到第一個字形
+    pub codepoint: Option<u32>,
+    pub outline: Outline,
+}
+
+/// 以字源構成字形名，只保留 PostScript 字形名允許的字符，如 `G0-523B` 爲 `G0_523B`
+pub fn glyph_name(source: &str) -> String {
+    let name: String = source
+        .chars()
+        .map(|ch| match ch {
+            'A'..='Z' | 'a'..='z' | '0'..='9' | '.' | '_' => ch,
+            _ => '_',
+        })
+        .take(63)
+        .collect();
+    match name.starts_with(|ch: char| ch.is_ascii_digit() || ch == '.') {
+        true => format!("_{}", name),
+        false => name,
+    }
+}
+
+/// 以 TrueType 輪廓生成字體
+///
+/// em 框映射到 `em_bottom`..`1 + em_bottom`（以 em 爲單位）的縱向範圍，基線位於 y = 0；每個字形寬一 em
+pub fn build_font(family: &str, glyphs: &[FontGlyph], em_bottom: f32) -> Result<Vec<u8>> {
+    if glyphs.len() >= u16::MAX as usize {
+        return Err(Error::Invalid(format!(
+            "字形過多，一個字體最多包含 {} 個字形",
+            u16::MAX - 1
+        )));
+    }
+
+    // 0 號字形爲 .notdef，畫一個方框
+    let unit = UNITS_PER_EM as f32;
+    let bottom = (em_bottom * unit).round() as i16;
+    let top = bottom + UNITS_PER_EM as i16;
+    let notdef = notdef_glyph(bottom, top);
+    let mut simple_glyphs = vec![notdef];
+    for glyph in glyphs {
+        simple_glyphs.push(SimpleGlyph::new(&glyph.outline, em_bottom));
+    }
+
+    // 字形名重複時附上序號
+    let mut names = vec![".notdef".to_string()];
+    let mut used = HashSet::new();
+    for glyph in glyphs {
+        let mut name = glyph.name.clone();
+        let mut count = 1;
+        while !used.insert(name.clone()) || name == ".notdef" {
+            name = format!("{}.{}", glyph.name, count);
+            count += 1;
+        }
+        names.push(name);
+    }
+
+    let mut mapping: Vec<(u32, u16)> = vec![];
+    let mut mapped = HashSet::new();
+    for (idx, glyph) in glyphs.iter().enumerate() {
+        if let Some(codepoint) = glyph.codepoint {
+            if mapped.insert(codepoint) {
+                mapping.push((codepoint, idx as u16 + 1));
+            }
+        }
+    }
+    mapping.sort();
+
+    let (glyf, loca) = glyf_table(&simple_glyphs);
+    let metrics = Metrics::new(&simple_glyphs, bottom, top);
+    let postscript_name: String = family
+        .chars()
+        .filter(|ch| ch.is_ascii_alphanumeric() || *ch == '-')
+        .take(63)
+        .collect();
+
+    let tables = [
+        (*b"OS/2", os2_table(&metrics, &mapping)),
+        (*b"cmap", cmap_table(&mapping)),
+        (*b"glyf", glyf),
+        (*b"head", head_table(&metrics)),
+        (*b"hhea", hhea_table(&metrics, simple_glyphs.len())),
+        (*b"hmtx", hmtx_table(&simple_glyphs)),
+        (*b"loca", loca),
+        (*b"maxp", maxp_table(&simple_glyphs)),
+        (*b"name", name_table(family, &postscript_name)),
+        (*b"post", post_table(&names)),
+    ];
+
+    Ok(assemble(&tables))
+}
+
+// TrueType 的簡單字形，坐標已換算爲字體單位
+struct SimpleGlyph {
+    // 每條輪廓的點 (x, y, 是否在曲線上)
+    contours: Vec<Vec<(i16, i16, bool)>>,
+}
+
+impl SimpleGlyph {
+    fn new(outline: &Outline, em_bottom: f32) -> Self {
+        let unit = UNITS_PER_EM as f32;
+        let scale = |(x, y): Point| (x * unit, (y + em_bottom) * unit);
+        let round = |(x, y): Point, on_curve| (x.round() as i16, y.round() as i16, on_curve);
+
+        let mut contours = vec![];
+        let mut contour: Vec<(i16, i16, bool)> = vec![];
+        let mut current = (0.0, 0.0);
+        for segment in &outline.segments {
+            match *segment {
+                Segment::MoveTo(p) => {
+                    close_contour(&mut contours, &mut contour);
+                    current = scale(p);
+                    contour.push(round(current, true));
+                }
+                Segment::LineTo(p) => {
+                    current = scale(p);
+                    contour.push(round(current, true));
+                }
+                Segment::QuadTo(p1, p) => {
+                    contour.push(round(scale(p1), false));
+                    current = scale(p);
+                    contour.push(round(current, true));
+                }
+                Segment::CubicTo(p1, p2, p) => {
+                    let end = scale(p);
+                    for (control, point) in cubic_to_quads(current, scale(p1), scale(p2), end) {
+                        contour.push(round(control, false));
+                        contour.push(round(point, true));
+                    }
+                    current = end;
+                }
+                Segment::Close => close_contour(&mut contours, &mut contour),
+            }
+        }
+        close_contour(&mut contours, &mut contour);
+
+        Self { contours }
+    }
+
+    fn points(&self) -> impl Iterator<Item = &(i16, i16, bool)> {
+        self.contours.iter().flatten()
+    }
+
+    // (xMin, yMin, xMax, yMax)，空字形爲 None
+    fn bounds(&self) -> Option<[i16; 4]> {
+        self.points().fold(None, |bounds, &(x, y, _)| {
+            let [x_min, y_min, x_max, y_max] = bounds.unwrap_or([x, y, x, y]);
+            Some([x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y)])
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let Some([x_min, y_min, x_max, y_max]) = self.bounds() else {
+            return vec![];
+        };
+
+        let mut data = vec![];
+        push_i16(&mut data, self.contours.len() as i16);
+        for value in [x_min, y_min, x_max, y_max] {
+            push_i16(&mut data, value);
+        }
+        let mut end = 0;
+        for contour in &self.contours {
+            end += contour.len();
+            push_u16(&mut data, end as u16 - 1);
+        }
+        // 不含指令
+        push_u16(&mut data, 0);
+
+        // 每點一個標記，坐標均以 16 位差值存儲
+        for &(_, _, on_curve) in self.points() {
+            data.push(on_curve as u8);
+        }
+        let mut previous = 0;
+        for &(x, _, _) in self.points() {
+            push_i16(&mut data, x.wrapping_sub(previous));
+            previous = x;
+        }
+        let mut previous = 0;
+        for &(_, y, _) in self.points() {
+            push_i16(&mut data, y.wrapping_sub(previous));
+            previous = y;
+        }
+
+        data
+    }
+}
+
+// 輪廓自動閉合，去掉與起點重合的終點
+fn close_contour(contours: &mut Vec<Vec<(i16, i16, bool)>>, contour: &mut Vec<(i16, i16, bool)>) {
+    if contour.len() > 1 && contour.first() == contour.last() {
+        contour.pop();
+    }
+    if !contour.is_empty() {
+        contours.push(std::mem::take(contour));
+    }
+}
+
+fn notdef_glyph(bottom: i16, top: i16) -> SimpleGlyph {
+    let (outer, inner) = (50, 100);
+    let rect = |left: i16, bottom: i16, right: i16, top: i16, clockwise: bool| {
+        let mut points = vec![
+            (left, bottom, true),
+            (left, top, true),
+            (right, top, true),
+            (right, bottom, true),
+        ];
+        if !clockwise {
+            points.reverse();
+        }
+        points
+    };
+    let size = UNITS_PER_EM as i16;
+    SimpleGlyph {
+        contours: vec![
+            rect(outer, bottom + outer, size - outer, top - outer, true),
+            rect(inner, bottom + inner, size - inner, top - inner, false),
+        ],
+    }
+}
+
+// 將三次曲線等分爲若干段，每段以一條二次曲線近似，返回各段的 (控制點, 終點)
+fn cubic_to_quads(p0: Point, p1: Point, p2: Point, p3: Point) -> Vec<(Point, Point)> {
+    let difference = |a: f32, b: f32, c: f32, d: f32| a - 3.0 * b + 3.0 * c - d;
+    let (dx, dy) = (
+        difference(p3.0, p2.0, p1.0, p0.0),
+        difference(p3.1, p2.1, p1.1, p0.1),
+    );
+    // 單條二次曲線的誤差約爲 √3/36 |p3 - 3p2 + 3p1 - p0|，分爲 n 段時減至 1/n³
+    let error = 3f32.sqrt() / 36.0 * (dx * dx + dy * dy).sqrt();
+    let count = (error / CUBIC_TOLERANCE).cbrt().ceil().clamp(1.0, 16.0) as usize;
+
+    let point = |t: f32| {
+        let mt = 1.0 - t;
+        let [a, b, c, d] = [mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t];
+        (
+            a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+            a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+        )
+    };
+    let derivative = |t: f32| {
+        let mt = 1.0 - t;
+        let [a, b, c] = [3.0 * mt * mt, 6.0 * mt * t, 3.0 * t * t];
+        (
+            a * (p1.0 - p0.0) + b * (p2.0 - p1.0) + c * (p3.0 - p2.0),
+            a * (p1.1 - p0.1) + b * (p2.1 - p1.1) + c * (p3.1 - p2.1),
+        )
+    };
+
+    (0..count)
+        .map(|idx| {
+            let (t0, t1) = (idx as f32 / count as f32, (idx + 1) as f32 / count as f32);
+            let third = (t1 - t0) / 3.0;
+            let (q0, q3) = (point(t0), point(t1));
+            let (d0, d1) = (derivative(t0), derivative(t1));
+            let q1 = (q0.0 + third * d0.0, q0.1 + third * d0.1);
+            let q2 = (q3.0 - third * d1.0, q3.1 - third * d1.1);
+            let control = (
+                (3.0 * (q1.0 + q2.0) - q0.0 - q3.0) / 4.0,
+                (3.0 * (q1.1 + q2.1) - q0.1 - q3.1) / 4.0,
+            );
+            (control, q3)
+        })
+        .collect()
+}
+
+// 各表共用的度量
+struct Metrics {
+    bounds: [i16; 4],
+    ascender: i16,
+    descender: i16,
+    min_lsb: i16,
+    min_rsb: i16,
+    max_points: u16,
+    max_contours: u16,
+}
+
+impl Metrics {
+    fn new(glyphs: &[SimpleGlyph], bottom: i16, top: i16) -> Self {
+        let all_bounds = glyphs.iter().filter_map(SimpleGlyph::bounds);
+        let bounds = all_bounds
+            .clone()
+            .reduce(|a, b| {
+                [
+                    a[0].min(b[0]),
+                    a[1].min(b[1]),
+                    a[2].max(b[2]),
+                    a[3].max(b[3]),
+                ]
+            })
+            .unwrap_or_default();
+        let advance = UNITS_PER_EM as i16;
+
+        Self {
+            bounds,
+            ascender: top,
+            descender: bottom,
+            min_lsb: all_bounds.clone().map(|b| b[0]).min().unwrap_or_default(),
+            min_rsb: all_bounds
+                .map(|b| advance.saturating_sub(b[2]))
+                .min()
+                .unwrap_or_default(),
+            max_points: glyphs
+                .iter()
+                .map(|glyph| glyph.points().count() as u16)
+                .max()
+                .unwrap_or_default(),
+            max_contours: glyphs
+                .iter()
+                .map(|glyph| glyph.contours.len() as u16)
+                .max()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend(value.to_be_bytes());
+}
+
+fn push_i16(data: &mut Vec<u8>, value: i16) {
+    data.extend(value.to_be_bytes());
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend(value.to_be_bytes());
+}
+
+fn glyf_table(glyphs: &[SimpleGlyph]) -> (Vec<u8>, Vec<u8>) {
+    let mut glyf = vec![];
+    let mut loca = vec![];
+    for glyph in glyphs {
+        push_u32(&mut loca, glyf.len() as u32);
+        glyf.extend(glyph.encode());
+        glyf.resize(glyf.len().next_multiple_of(4), 0);
+    }
+    push_u32(&mut loca, glyf.len() as u32);
+
+    (glyf, loca)
+}
+
+fn head_table(metrics: &Metrics) -> Vec<u8> {
+    let mut data = vec![];
+    push_u32(&mut data, 0x0001_0000);
+    // fontRevision 1.0
+    push_u32(&mut data, 0x0001_0000);
+    // checkSumAdjustment 在組裝時填入
+    push_u32(&mut data, 0);
+    push_u32(&mut data, 0x5F0F_3CF5);
+    // 基線位於 y = 0，左側位於 x = 0
+    push_u16(&mut data, 0b11);
+    push_u16(&mut data, UNITS_PER_EM);
+    // 創建與修改時間
+    data.extend([0; 16]);
+    for value in metrics.bounds {
+        push_i16(&mut data, value);
+    }
+    // macStyle、lowestRecPPEM、fontDirectionHint
+    push_u16(&mut data, 0);
+    push_u16(&mut data, 8);
+    push_i16(&mut data, 2);
+    // 長 loca、glyf 格式 0
+    push_i16(&mut data, 1);
+    push_i16(&mut data, 0);
+
+    data
+}
+
+fn hhea_table(metrics: &Metrics, glyph_count: usize) -> Vec<u8> {
+    let mut data = vec![];
+    push_u32(&mut data, 0x0001_0000);
+    push_i16(&mut data, metrics.ascender);
+    push_i16(&mut data, metrics.descender);
+    // lineGap
+    push_i16(&mut data, 0);
+    push_u16(&mut data, UNITS_PER_EM);
+    push_i16(&mut data, metrics.min_lsb);
+    push_i16(&mut data, metrics.min_rsb);
+    push_i16(&mut data, metrics.bounds[2]);
+    // caretSlopeRise、caretSlopeRun、caretOffset 與保留字段、metricDataFormat
+    push_i16(&mut data, 1);
+    data.extend([0; 14]);
+    push_u16(&mut data, glyph_count as u16);
+
+    data
+}
+
+fn hmtx_table(glyphs: &[SimpleGlyph]) -> Vec<u8> {
+    let mut data = vec![];
+    for glyph in glyphs {
+        push_u16(&mut data, UNITS_PER_EM);
+        push_i16(&mut data, glyph.bounds().map(|b| b[0]).unwrap_or_default());
+    }
+
+    data
+}
+
+fn maxp_table(glyphs: &[SimpleGlyph]) -> Vec<u8> {
+    let metrics = Metrics::new(glyphs, 0, 0);
+    let mut data = vec![];
+    push_u32(&mut data, 0x0001_0000);
+    push_u16(&mut data, glyphs.len() as u16);
+    push_u16(&mut data, metrics.max_points);
+    push_u16(&mut data, metrics.max_contours);
+    // 沒有複合字形與指令，maxZones 爲 2，其餘均爲 0
+    push_u16(&mut data, 0);
+    push_u16(&mut data, 0);
+    push_u16(&mut data, 2);
+    data.extend([0; 16]);
+
+    data
+}
+
+// 格式 4 覆蓋基本多文種平面，格式 12 覆蓋所有碼位；格式 4 的段數超出其長度上限時只用格式 12
+fn cmap_table(mapping: &[(u32, u16)]) -> Vec<u8> {
+    let format4 = cmap_format4(mapping).unwrap_or_default();
+    let format12 = cmap_format12(mapping);
+
+    let mut records = vec![];
+    let header_len = if format4.is_empty() {
+        4 + 2 * 8
+    } else {
+        4 + 3 * 8
+    };
+    let offset12 = (header_len + format4.len()) as u32;
+    records.push((0, 4, offset12));
+    if !format4.is_empty() {
+        records.push((3, 1, header_len as u32));
+    }
+    records.push((3, 10, offset12));
+
+    let mut data = vec![];
+    push_u16(&mut data, 0);
+    push_u16(&mut data, records.len() as u16);
+    for (platform, encoding, offset) in records {
+        push_u16(&mut data, platform);
+        push_u16(&mut data, encoding);
+        push_u32(&mut data, offset);
+    }
+    data.extend(format4);
+    data.extend(format12);
+
+    data
+}
+
+// 碼位與字形號均連續的一段 (首碼位, 末碼位, 首字形號)
+fn cmap_runs(mapping: &[(u32, u16)]) -> Vec<(u32, u32, u16)> {
+    let mut runs: Vec<(u32, u32, u16)> = vec![];
+    for &(codepoint, glyph_id) in mapping {
+        match runs.last_mut() {
+            Some((start, end, start_id))
+                if codepoint == *end + 1
+                    && glyph_id as u32 == *start_id as u32 + (codepoint - *start) =>
+            {
+                *end = codepoint;
+            }
+            _ => runs.push((codepoint, codepoint, glyph_id)),
+        }
+    }
+
+    runs
+}
+
+fn cmap_format4(mapping: &[(u32, u16)]) -> Option<Vec<u8>> {
+    let bmp = mapping
+        .iter()
+        .copied()
+        .filter(|&(codepoint, _)| codepoint < 0xFFFF)
+        .collect::<Vec<_>>();
+    let mut runs = cmap_runs(&bmp);
+    // 末段須爲 0xFFFF
+    runs.push((0xFFFF, 0xFFFF, 0));
+
+    // 子表長度以 16 位記錄，約 8190 段爲上限
+    let seg_count = u16::try_from(runs.len()).ok()?;
+    let length = seg_count.checked_mul(8)?.checked_add(16)?;
+    let entry_selector = 15 - seg_count.leading_zeros() as u16;
+    let search_range = 2 << entry_selector;
+
+    let mut data = vec![];
+    push_u16(&mut data, 4);
+    push_u16(&mut data, length);
+    push_u16(&mut data, 0);
+    push_u16(&mut data, seg_count * 2);
+    push_u16(&mut data, search_range);
+    push_u16(&mut data, entry_selector);
+    push_u16(&mut data, seg_count * 2 - search_range);
+    for &(_, end, _) in &runs {
+        push_u16(&mut data, end as u16);
+    }
+    push_u16(&mut data, 0);
+    for &(start, _, _) in &runs {
+        push_u16(&mut data, start as u16);
+    }
+    for &(start, end, start_id) in &runs {
+        // 0xFFFF 段映射到 .notdef
+        let delta = match end {
+            0xFFFF => 1,
+            _ => start_id.wrapping_sub(start as u16),
+        };
+        push_u16(&mut data, delta);
+    }
+    data.extend(vec![0; runs.len() * 2]);
+
+    Some(data)
+}
+
+fn cmap_format12(mapping: &[(u32, u16)]) -> Vec<u8> {
+    let runs = cmap_runs(mapping);
+    let mut data = vec![];
+    push_u16(&mut data, 12);
+    push_u16(&mut data, 0);
+    push_u32(&mut data, 16 + 12 * runs.len() as u32);
+    push_u32(&mut data, 0);
+    push_u32(&mut data, runs.len() as u32);
+    for (start, end, start_id) in runs {
+        push_u32(&mut data, start);
+        push_u32(&mut data, end);
+        push_u32(&mut data, start_id as u32);
+    }
+
+    data
+}
+
+fn name_table(family: &str, postscript_name: &str) -> Vec<u8> {
+    let records = [
+        (1, family.to_string()),
+        (2, "Regular".to_string()),
+        (3, format!("{}-Regular", postscript_name)),
+        (4, family.to_string()),
+        (5, "Version 1.000".to_string()),
+        (6, postscript_name.to_string()),
+    ];
+
+    let mut strings = vec![];
+    let mut data = vec![];
+    push_u16(&mut data, 0);
+    push_u16(&mut data, records.len() as u16);
+    push_u16(&mut data, 6 + 12 * records.len() as u16);
+    for (name_id, value) in records {
+        let encoded = value
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect::<Vec<_>>();
+        // Windows 平臺、Unicode BMP 編碼、美國英語
+        for value in [
+            3,
+            1,
+            0x0409,
+            name_id,
+            encoded.len() as u16,
+            strings.len() as u16,
+        ] {
+            push_u16(&mut data, value);
+        }
+        strings.extend(encoded);
+    }
+    data.extend(strings);
+
+    data
+}
+
+fn os2_table(metrics: &Metrics, mapping: &[(u32, u16)]) -> Vec<u8> {
+    let first = mapping
+        .first()
+        .map(|&(codepoint, _)| codepoint)
+        .unwrap_or_default();
+    let last = mapping
+        .last()
+        .map(|&(codepoint, _)| codepoint)
+        .unwrap_or_default();
+    let (ascender, descender) = (metrics.ascender, metrics.descender);
+
+    let mut data = vec![];
+    push_u16(&mut data, 4);
+    // xAvgCharWidth、usWeightClass、usWidthClass、fsType
+    push_i16(&mut data, UNITS_PER_EM as i16);
+    push_u16(&mut data, 400);
+    push_u16(&mut data, 5);
+    push_u16(&mut data, 0);
+    // 上下標與刪除線的尺寸與位置
+    for value in [650, 600, 0, 75, 650, 600, 0, 350, 50, 250] {
+        push_i16(&mut data, value);
+    }
+    // sFamilyClass、panose
+    push_i16(&mut data, 0);
+    data.extend([0; 10]);
+    // ulUnicodeRange：中日韓統一表意文字（第 59 位），另有補充平面的碼位時加第 57 位
+    let supplementary = last > 0xFFFF;
+    push_u32(&mut data, 0);
+    push_u32(
+        &mut data,
+        1 << (59 - 32) | (supplementary as u32) << (57 - 32),
+    );
+    push_u32(&mut data, 0);
+    push_u32(&mut data, 0);
+    data.extend(b"NONE");
+    // fsSelection：REGULAR
+    push_u16(&mut data, 0x40);
+    push_u16(&mut data, first.min(0xFFFF) as u16);
+    push_u16(&mut data, last.min(0xFFFF) as u16);
+    push_i16(&mut data, ascender);
+    push_i16(&mut data, descender);
+    push_i16(&mut data, 0);
+    push_u16(&mut data, metrics.bounds[3].max(ascender) as u16);
+    push_u16(&mut data, metrics.bounds[1].min(descender).unsigned_abs());
+    // ulCodePageRange 留空
+    push_u32(&mut data, 0);
+    push_u32(&mut data, 0);
+    // sxHeight、sCapHeight、usDefaultChar、usBreakChar、usMaxContext
+    push_i16(&mut data, 0);
+    push_i16(&mut data, 0);
+    push_u16(&mut data, 0);
+    push_u16(&mut data, 0x20);
+    push_u16(&mut data, 1);
+
+    data
+}
+
+// 版本 2.0，除 .notdef 外的字形名均存於表內；字形名超出 2.0 版的上限時改用不含字形名的 3.0 版
+fn post_table(names: &[String]) -> Vec<u8> {
+    let with_names = names.len() - 1 <= POST_MAX_NAMES;
+    let mut data = vec![];
+    push_u32(
+        &mut data,
+        if with_names { 0x0002_0000 } else { 0x0003_0000 },
+    );
+    // italicAngle、underlinePosition、underlineThickness
+    push_u32(&mut data, 0);
+    push_i16(&mut data, -100);
+    push_i16(&mut data, 50);
+    // isFixedPitch 與內存用量
+    push_u32(&mut data, 1);
+    data.extend([0; 16]);
+    if !with_names {
+        return data;
+    }
+
+    push_u16(&mut data, names.len() as u16);
+    for idx in 0..names.len() {
+        // 標準名表中 0 號爲 .notdef，自定義名從 258 起
+        let index = match idx {
+            0 => 0,
+            idx => 257 + idx as u16,
+        };
+        push_u16(&mut data, index);
+    }
+    for name in &names[1..] {
+        data.push(name.len() as u8);
+        data.extend(name.as_bytes());
+    }
+
+    data
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+// 表須按標籤排列
+fn assemble(tables: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let num_tables = tables.len() as u16;
+    let entry_selector = 15 - num_tables.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+
+    let mut font = vec![];
+    push_u32(&mut font, 0x0001_0000);
+    push_u16(&mut font, num_tables);
+    push_u16(&mut font, search_range);
+    push_u16(&mut font, entry_selector);
+    push_u16(&mut font, num_tables * 16 - search_range);
+
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = 0;
+    for (tag, table) in tables {
+        if tag == b"head" {
+            head_offset = offset;
+        }
+        font.extend(tag);
+        push_u32(&mut font, checksum(table));
+        push_u32(&mut font, offset as u32);
+        push_u32(&mut font, table.len() as u32);
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in tables {
+        font.extend(table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+
+    let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&font));
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+
+    font
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 統計輪廓中的二次曲線數
+    #[derive(Default)]
+    struct QuadCounter(usize);
+
+    impl ttf_parser::OutlineBuilder for QuadCounter {
+        fn move_to(&mut self, _: f32, _: f32) {}
+        fn line_to(&mut self, _: f32, _: f32) {}
+        fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {
+            self.0 += 1;
+        }
+        fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+        fn close(&mut self) {}
+    }
+
+    #[test]
+    fn build_chart_font() {
+        let square = Outline::parse("M.1 .1H.9V.9H.1Z").unwrap();
+        let curve = Outline::parse("M.1 .1C.1 .9 .9 .9 .9 .1Z").unwrap();
+        let glyphs = [
+            FontGlyph {
+                name: glyph_name("T3-2144"),
+                codepoint: Some(0x4E00),
+                outline: square.clone(),
+            },
+            FontGlyph {
+                name: glyph_name("T5-4B27"),
+                codepoint: Some(0x20000),
+                outline: curve,
+            },
+            // 同一碼位的第二個字形不進入 cmap
+            FontGlyph {
+                name: glyph_name("T3-2144"),
+                codepoint: Some(0x4E00),
+                outline: square,
+            },
+        ];
+        let data = build_font("Chart T", &glyphs, -0.12).unwrap();
+
+        let face = ttf_parser::Face::parse(&data, 0).unwrap();
+        assert_eq!(face.number_of_glyphs(), 4);
+        assert_eq!(face.units_per_em(), 1000);
+        assert_eq!(face.ascender(), 880);
+        assert_eq!(face.descender(), -120);
+
+        let id = face.glyph_index('一').unwrap();
+        assert_eq!(face.glyph_name(id), Some("T3_2144"));
+        let bbox = face.glyph_bounding_box(id).unwrap();
+        assert_eq!(
+            (bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max),
+            (100, -20, 900, 780)
+        );
+
+        let id = face.glyph_index('\u{20000}').unwrap();
+        assert_eq!(face.glyph_name(id), Some("T5_4B27"));
+        // 三次曲線近似爲數段二次曲線，最高點在 0.7 em，即基線上 580 單位
+        let mut counter = QuadCounter::default();
+        let bbox = face.outline_glyph(id, &mut counter).unwrap();
+        assert!(counter.0 > 1);
+        assert!((bbox.y_max - 580).abs() <= 2);
+        assert_eq!(face.glyph_name(ttf_parser::GlyphId(3)), Some("T3_2144.1"));
+        assert_eq!(checksum(&data), 0xB1B0_AFBA);
+    }
+
+    #[test]
+    fn post_name_limit() {
+        let names = |count: usize| {
+            std::iter::once(".notdef".to_string())
+                .chain((1..=count).map(|idx| format!("g{}", idx)))
+                .collect::<Vec<_>>()
+        };
+
+        // 最後一個字形名的序號爲 32767
+        let data = post_table(&names(POST_MAX_NAMES));
+        let post = ttf_parser::post::Table::parse(&data).unwrap();
+        assert_eq!(&data[..4], [0, 2, 0, 0]);
+        assert_eq!(
+            post.glyph_name(ttf_parser::GlyphId(POST_MAX_NAMES as u16)),
+            Some("g32510")
+        );
+        assert_eq!(
+            data[34 + 2 * POST_MAX_NAMES..36 + 2 * POST_MAX_NAMES],
+            [0x7F, 0xFF]
+        );
+
+        // 再多一個字形則不存字形名
+        let data = post_table(&names(POST_MAX_NAMES + 1));
+        let post = ttf_parser::post::Table::parse(&data).unwrap();
+        assert_eq!(&data[..4], [0, 3, 0, 0]);
+        assert_eq!(data.len(), 32);
+        assert_eq!(post.names().count(), 0);
+    }
+
+    #[test]
+    fn large_cmap() {
+        let lookup = |data: &[u8], codepoint| {
+            let cmap = ttf_parser::cmap::Table::parse(data).unwrap();
+            let subtables = cmap.subtables.into_iter().collect::<Vec<_>>();
+            let ids = subtables
+                .iter()
+                .map(|subtable| subtable.glyph_index(codepoint).map(|id| id.0))
+                .collect::<Vec<_>>();
+            (subtables.len(), ids)
+        };
+
+        let mapping = [(0x4E00, 1), (0x4E01, 2), (0x20000, 3)];
+        let data = cmap_table(&mapping);
+        assert_eq!(lookup(&data, 0x4E01), (3, vec![Some(2); 3]));
+
+        // 碼位互不相連時每個碼位各佔一段，超出格式 4 的上限
+        let mapping = (0..9000)
+            .map(|idx| (0x4E00 + idx * 2, idx as u16 + 1))
+            .collect::<Vec<_>>();
+        assert_eq!(cmap_format4(&mapping), None);
+        let data = cmap_table(&mapping);
+        assert_eq!(lookup(&data, 0x4E00 + 8999 * 2), (2, vec![Some(9000); 2]));
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::{
+    layout::LayoutProfile,
+    parse::{ChartCode, ChartGraph, ChartSource},
+};
+
+/// 頁面上有位置的元素
+pub trait Positioned {
+    /// 左端的橫坐標
+    fn x(&self) -> f64;
+    /// 基線的縱坐標
+    fn y(&self) -> f64;
+}
+
+impl Positioned for ChartCode {
+    fn x(&self) -> f64 {
+        self.x_min
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+impl Positioned for ChartSource {
+    fn x(&self) -> f64 {
+        self.x_min
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+impl Positioned for ChartGraph<'_> {
+    fn x(&self) -> f64 {
+        self.x
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
+
+impl<T: Positioned + ?Sized> Positioned for &T {
+    fn x(&self) -> f64 {
+        (**self).x()
+    }
+
+    fn y(&self) -> f64 {
+        (**self).y()
+    }
+}
+
+/// 一維上聚在一起的一段坐標
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Band {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+impl Band {
+    pub fn contains(&self, value: f64) -> bool {
+        self.start <= value && value <= self.end
+    }
+}
+
+/// 分隔距離：不超過 `min_gap` 的最大差值與超過它的最小差值之間的中點，
+/// 即數據中抖動與真正間隔之間的空隙；兩者缺一時取 `min_gap`
+pub fn split_gap(diffs: impl IntoIterator<Item = f64>, min_gap: f64) -> f64 {
+    let (jitter, spacing) = diffs
+        .into_iter()
+        .fold((None, None), |(jitter, spacing), diff: f64| {
+            if diff <= min_gap {
+                (Some(diff.max(jitter.unwrap_or(diff))), spacing)
+            } else {
+                (jitter, Some(diff.min(spacing.unwrap_or(diff))))
+            }
+        });
+
+    match (jitter, spacing) {
+        (Some(jitter), Some(spacing)) => (jitter + spacing) / 2.0,
+        _ => min_gap,
+    }
+}
+
+/// 將坐標排序後，相鄰之差超過分隔距離處分段
+pub fn cluster(values: impl IntoIterator<Item = f64>, min_gap: f64) -> Vec<Band> {
+    let sorted = values.into_iter().sorted_by(f64::total_cmp).collect_vec();
+    let gap = split_gap(sorted.iter().tuple_windows().map(|(a, b)| b - a), min_gap);
+
+    let mut bands: Vec<Band> = vec![];
+    for value in sorted {
+        match bands.last_mut() {
+            Some(band) if value - band.end <= gap => {
+                band.end = value;
+                band.count += 1;
+            }
+            _ => bands.push(Band {
+                start: value,
+                end: value,
+                count: 1,
+            }),
+        }
+    }
+
+    bands
+}
+
+/// 由編碼標籤的位置得出的頁面網格：每格從該格編碼的左端與基線起，至下一列、下一行爲止
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageGrid {
+    pub columns: Vec<Band>,
+    pub rows: Vec<Band>,
+}
+
+impl PageGrid {
+    pub fn from_codes(codes: &[ChartCode], layout: &LayoutProfile) -> Self {
+        Self {
+            columns: cluster(codes.iter().map(Positioned::x), layout.column_tolerance),
+            rows: cluster(codes.iter().map(Positioned::y), layout.row_tolerance),
+        }
+    }
+
+    pub fn column_of(&self, x: f64) -> Option<usize> {
+        self.columns.iter().rposition(|band| band.start <= x)
+    }
+
+    pub fn row_of(&self, y: f64) -> Option<usize> {
+        self.rows.iter().rposition(|band| band.start <= y)
+    }
+
+    /// 元素所在的格子 (列, 行)，位於首列左側或首行上方時爲 `None`
+    pub fn cell_of<T: Positioned>(&self, item: &T) -> Option<(usize, usize)> {
+        Some((self.column_of(item.x())?, self.row_of(item.y())?))
+    }
+
+    /// 各格子中的編碼在 `codes` 中的下標，同一格有多個編碼時取第一個
+    pub fn index_codes(&self, codes: &[ChartCode]) -> HashMap<(usize, usize), usize> {
+        let mut cells = HashMap::new();
+        for (idx, code) in codes.iter().enumerate() {
+            if let Some(cell) = self.cell_of(code) {
+                cells.entry(cell).or_insert(idx);
+            }
+        }
+
+        cells
+    }
+
+    /// 按行、列排序，同一格內再按縱、橫坐標排序
+    pub fn sort_by_rows<T: Positioned>(&self, items: &mut [T]) {
+        items.sort_by(|a, b| {
+            let key = |item: &T| (self.row_of(item.y()), self.column_of(item.x()));
+            key(a)
+                .cmp(&key(b))
+                .then(a.y().total_cmp(&b.y()))
+                .then(a.x().total_cmp(&b.x()))
+        });
+    }
+
+    /// 按列、行排序，同一格內再按橫、縱坐標排序
+    pub fn sort_by_columns<T: Positioned>(&self, items: &mut [T]) {
+        items.sort_by(|a, b| {
+            let key = |item: &T| (self.column_of(item.x()), self.row_of(item.y()));
+            key(a)
+                .cmp(&key(b))
+                .then(a.x().total_cmp(&b.x()))
+                .then(a.y().total_cmp(&b.y()))
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cluster_by_gaps() {
+        // 兩列之內略有抖動
+        let bands = cluster([50.0, 50.2, 110.0, 50.1, 110.3, 170.0], 2.0);
+        assert_eq!(bands.len(), 3);
+        assert_eq!(
+            (bands[0].start, bands[0].end, bands[0].count),
+            (50.0, 50.2, 3)
+        );
+        assert_eq!(bands[1].count, 2);
+
+        // 沒有抖動時仍以最小間隔分段
+        assert_eq!(cluster([0.0, 40.0, 80.0, 0.0], 5.0).len(), 3);
+        assert_eq!(split_gap([0.3, 40.0, 41.0], 5.0), 20.15);
+
+        let grid = PageGrid {
+            columns: cluster([50.0, 150.0], 2.0),
+            rows: cluster([100.0, 200.0], 5.0),
+        };
+        assert_eq!(grid.column_of(120.0), Some(0));
+        assert_eq!(grid.row_of(210.0), Some(1));
+        assert_eq!(grid.column_of(10.0), None);
+    }
+
+    struct Point(f64, f64);
+
+    impl Positioned for Point {
+        fn x(&self) -> f64 {
+            self.0
+        }
+
+        fn y(&self) -> f64 {
+            self.1
+        }
+    }
+
+    #[test]
+    fn sort_by_bands() {
+        let grid = PageGrid {
+            columns: cluster([50.0, 150.0], 2.0),
+            rows: cluster([100.0, 200.0], 5.0),
+        };
+        // 同一行內的縱坐標略有參差，按閾值比較時不可傳遞
+        let mut points = vec![
+            Point(160.0, 131.0),
+            Point(60.0, 230.0),
+            Point(70.0, 132.0),
+            Point(160.0, 130.0),
+            Point(60.0, 130.5),
+            Point(10.0, 300.0),
+        ];
+
+        grid.sort_by_rows(&mut points);
+        let order = points.iter().map(|p| (p.0, p.1)).collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                (60.0, 130.5),
+                (70.0, 132.0),
+                (160.0, 130.0),
+                (160.0, 131.0),
+                (10.0, 300.0),
+                (60.0, 230.0),
+            ]
+        );
+
+        grid.sort_by_columns(&mut points);
+        let order = points.iter().map(|p| (p.0, p.1)).collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                (10.0, 300.0),
+                (60.0, 130.5),
+                (70.0, 132.0),
+                (60.0, 230.0),
+                (160.0, 130.0),
+                (160.0, 131.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    fs,
+    ops::Bound,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    blocks::{parse_block, Block},
+    error::{Error, Result},
+    image_gen::RenderOptions,
+    layout::LayoutProfile,
+    output::OutputFormat,
+    run,
+    utils::{parse_codepoint_range, parse_page_range},
+    Backend, ConvertOptions, RunSummary,
+};
+
+/// 批量轉換的任務清單（TOML）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobFile {
+    #[serde(default)]
+    pub defaults: JobDefaults,
+    #[serde(default, rename = "job")]
+    pub jobs: Vec<Job>,
+}
+
+/// 各任務共用的默認值
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobDefaults {
+    pub input_dir: Option<PathBuf>,
+    pub output_dir: Option<PathBuf>,
+    pub limit: Option<u32>,
+    pub format: Option<String>,
+    pub backend: Option<String>,
+    pub lenient: Option<bool>,
+    pub layout: Option<String>,
+    pub order: Option<String>,
+    pub columns: Option<String>,
+    pub svg_metadata: Option<bool>,
+    pub render: Option<RenderOptions>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub name: Option<String>,
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub codepoints: Option<String>,
+    pub block: Option<String>,
+    pub pages: Option<String>,
+    pub limit: Option<u32>,
+    pub format: Option<String>,
+    pub backend: Option<String>,
+    pub lenient: Option<bool>,
+    pub layout: Option<String>,
+    pub order: Option<String>,
+    pub columns: Option<String>,
+    pub svg_metadata: Option<bool>,
+    pub render: Option<RenderOptions>,
+}
+
+#[derive(Debug)]
+pub struct JobSummary {
+    pub name: String,
+    pub output: PathBuf,
+    pub result: Result<RunSummary, String>,
+    pub elapsed: Duration,
+}
+
+impl JobFile {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let text = fs::read_to_string(&path).map_err(|err| Error::file(path.as_ref(), err))?;
+        Ok(toml::from_str(&text)?)
+    }
+
+    /// 依序執行所有任務，單個任務失敗不影響其餘任務。相對路徑以 `base_dir` 爲起點
+    pub fn run_all<P: AsRef<Path>>(&self, base_dir: P) -> Vec<JobSummary> {
+        let base_dir = base_dir.as_ref();
+
+        self.jobs
+            .iter()
+            .map(|job| {
+                let name = job.name();
+                let input = self.resolve(base_dir, self.defaults.input_dir.as_ref(), &job.input);
+                let output = self.resolve(base_dir, self.defaults.output_dir.as_ref(), &job.output);
+                println!("[{}] 正在處理文件 {} 中:", name, input.display());
+
+                let start = Instant::now();
+                let result = job
+                    .options(&self.defaults, base_dir, &output)
+                    .and_then(|options| {
+                        if let Some(parent) = output.parent() {
+                            fs::create_dir_all(parent).map_err(|err| Error::file(parent, err))?;
+                        }
+                        run(
+                            &input.to_string_lossy(),
+                            &output.to_string_lossy(),
+                            &options,
+                        )
+                    })
+                    .map_err(|err| err.to_string());
+                println!();
+
+                JobSummary {
+                    name,
+                    output,
+                    result,
+                    elapsed: start.elapsed(),
+                }
+            })
+            .collect()
+    }
+
+    fn resolve(&self, base_dir: &Path, dir: Option<&PathBuf>, path: &Path) -> PathBuf {
+        match dir {
+            Some(dir) => base_dir.join(dir).join(path),
+            None => base_dir.join(path),
+        }
+    }
+}
+
+impl Job {
+    pub fn name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            self.output
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default()
+        })
+    }
+
+    /// 版式文件的相對路徑以 `base_dir` 爲起點
+    pub fn options(
+        &self,
+        defaults: &JobDefaults,
+        base_dir: &Path,
+        output: &Path,
+    ) -> Result<ConvertOptions> {
+        let codepoints = self
+            .codepoints
+            .as_deref()
+            .map(parse_codepoint_range)
+            .transpose()?;
+        let block = self.block.as_deref().map(parse_block).transpose()?;
+        let mut options = ConvertOptions::new(codepoints.or_else(|| block.map(Block::range)));
+
+        options.pages = match &self.pages {
+            Some(pages) => parse_page_range(pages)?,
+            None => (Bound::Unbounded, Bound::Unbounded),
+        };
+        if let Some(limit) = self.limit.or(defaults.limit) {
+            options.limit = limit;
+        }
+        options.format = match self.format.as_ref().or(defaults.format.as_ref()) {
+            Some(format) => format.parse()?,
+            None => OutputFormat::from_path(output).unwrap_or_default(),
+        };
+        options.backend = match self.backend.as_ref().or(defaults.backend.as_ref()) {
+            Some(backend) => backend.parse::<Backend>()?,
+            None => Backend::default(),
+        };
+        options.lenient = self.lenient.or(defaults.lenient).unwrap_or_default();
+        if let Some(layout) = self.layout.as_ref().or(defaults.layout.as_ref()) {
+            options.layout = Some(LayoutProfile::resolve(layout, base_dir)?);
+        }
+        if let Some(order) = self.order.as_ref().or(defaults.order.as_ref()) {
+            options.order = order.parse()?;
+        }
+        if let Some(columns) = self.columns.as_ref().or(defaults.columns.as_ref()) {
+            options.columns = columns.parse()?;
+        }
+        options.svg_metadata = self
+            .svg_metadata
+            .or(defaults.svg_metadata)
+            .unwrap_or_default();
+        if let Some(render) = self.render.as_ref().or(defaults.render.as_ref()) {
+            options.render = render.clone();
+        }
+
+        Ok(options)
+    }
+}
+
+/// 以表格形式打印每個任務的結果
+pub fn print_summaries(summaries: &[JobSummary]) {
+    println!("任務\t狀態\t頁數\t碼位數\t字形數\t問題\t用時\t輸出");
+    for summary in summaries {
+        let elapsed = format!("{:.1}s", summary.elapsed.as_secs_f64());
+        match &summary.result {
+            Ok(res) => println!(
+                "{}\t成功\t{}\t{}\t{}\t{}\t{}\t{}",
+                summary.name,
+                res.pages,
+                res.entries,
+                res.glyphs,
+                res.problems,
+                elapsed,
+                summary.output.display()
+            ),
+            Err(err) => println!("{}\t失敗\t-\t-\t-\t-\t{}\t{}", summary.name, elapsed, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::image_gen::Color;
+
+    #[test]
+    fn job_options() {
+        let job_file: JobFile = toml::from_str(
+            r##"
+            [defaults]
+            limit = 50
+
+            [defaults.render]
+            size = 64
+            background = "#F0F0F0"
+
+            [[job]]
+            input = "U20000.pdf"
+            output = "ext-b.json"
+            pages = "1-20"
+
+            [[job]]
+            input = "ext-c.pdf"
+            output = "ext-c.csv"
+            block = "ext-c"
+            layout = "unicode-15"
+            "##,
+        )
+        .unwrap();
+
+        let job = &job_file.jobs[0];
+        let options = job
+            .options(&job_file.defaults, Path::new("."), Path::new("ext-b.json"))
+            .unwrap();
+        assert_eq!(job.name(), "ext-b");
+        assert_eq!(options.codepoints, None);
+        assert_eq!(options.pages, (Bound::Included(1), Bound::Included(20)));
+        assert_eq!(options.limit, 50);
+        assert_eq!(options.format, OutputFormat::Json);
+        assert_eq!(options.layout, None);
+        assert_eq!(options.render.size, 64);
+        assert_eq!(options.render.background, Color(0xF0, 0xF0, 0xF0));
+        assert_eq!(options.render.padding, 0);
+
+        let job = &job_file.jobs[1];
+        let options = job
+            .options(&job_file.defaults, Path::new("."), Path::new("ext-c.csv"))
+            .unwrap();
+        assert_eq!(options.codepoints, Some(0x2A700..=0x2B73F));
+        assert_eq!(options.layout, Some(LayoutProfile::unicode15()));
+    }
+}
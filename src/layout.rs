@@ -0,0 +1,355 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+/// 字號在 `size ± tolerance` 之內即匹配
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SizeRule {
+    pub size: f64,
+    pub tolerance: f64,
+}
+
+impl SizeRule {
+    pub const fn new(size: f64, tolerance: f64) -> Self {
+        Self { size, tolerance }
+    }
+
+    pub fn matches(&self, size: f64) -> bool {
+        (size - self.size).abs() <= self.tolerance
+    }
+}
+
+/// 圖表頁面的版式：依字號區分編碼、字源與字形，以及分組所用的距離閾值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LayoutProfile {
+    /// 未填時取版式文件的文件名
+    #[serde(default)]
+    pub name: String,
+    /// 編碼標籤，如 `4E00`
+    pub code: SizeRule,
+    /// 字源標籤，如 `G0-523B`
+    pub source: SizeRule,
+    /// 字號大於此值且不屬於標籤的字符視爲字形
+    pub min_glyph_size: f64,
+    /// 同一編碼內相鄰字符的最大橫向間距
+    pub code_gap: f64,
+    /// 同一字源內相鄰字符的最大橫向間距
+    pub source_gap: f64,
+    /// 縱坐標相差在此之內視爲同一行，行內按橫坐標排序
+    pub row_tolerance: f64,
+    /// 相鄰兩行的最小縱向間距，字源須在字形基線之下一個字號加此距離之內
+    pub row_gap: f64,
+    /// 橫坐標相差在此之內視爲同一列
+    pub column_tolerance: f64,
+}
+
+impl LayoutProfile {
+    /// 內置版式的名稱
+    pub const BUILTIN: &'static [&'static str] = &["unicode-15", "legacy", "iso10646"];
+
+    /// Unicode 15.0 起的代碼圖表
+    pub fn unicode15() -> Self {
+        Self {
+            name: "unicode-15".to_string(),
+            code: SizeRule::new(9.9998, 0.01),
+            source: SizeRule::new(6.0, 0.01),
+            min_glyph_size: 6.0,
+            code_gap: 10.0,
+            source_gap: 7.0,
+            row_tolerance: 5.0,
+            row_gap: 10.0,
+            column_tolerance: 2.0,
+        }
+    }
+
+    /// 較早的代碼圖表，標籤字號略有出入
+    pub fn legacy() -> Self {
+        Self {
+            name: "legacy".to_string(),
+            code: SizeRule::new(10.0, 0.1),
+            source: SizeRule::new(6.0, 0.25),
+            min_glyph_size: 6.5,
+            row_tolerance: 6.0,
+            column_tolerance: 3.0,
+            ..Self::unicode15()
+        }
+    }
+
+    /// ISO/IEC 10646 的多列圖表，每個碼位並列多個字源，標籤較小、排列較密
+    pub fn iso10646() -> Self {
+        Self {
+            name: "iso10646".to_string(),
+            code: SizeRule::new(8.0, 0.5),
+            source: SizeRule::new(5.0, 0.5),
+            min_glyph_size: 9.0,
+            code_gap: 8.0,
+            source_gap: 5.0,
+            row_tolerance: 4.0,
+            row_gap: 8.0,
+            column_tolerance: 2.0,
+        }
+    }
+
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "unicode-15" => Some(Self::unicode15()),
+            "legacy" => Some(Self::legacy()),
+            "iso10646" => Some(Self::iso10646()),
+            _ => None,
+        }
+    }
+
+    /// 從 TOML 文件讀取，未填的項目取 `unicode-15` 的值
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|err| Error::file(path, err))?;
+        let mut profile: LayoutProfile = toml::from_str(&text)?;
+        if profile.name.is_empty() {
+            profile.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+        }
+
+        Ok(profile)
+    }
+
+    /// 內置版式名稱或版式文件的路徑，相對路徑以 `base_dir` 爲起點
+    pub fn resolve<P: AsRef<Path>>(name_or_path: &str, base_dir: P) -> Result<Self> {
+        match Self::builtin(name_or_path) {
+            Some(profile) => Ok(profile),
+            None => Self::load(base_dir.as_ref().join(name_or_path)),
+        }
+    }
+
+    pub fn is_code(&self, size: f64) -> bool {
+        self.code.matches(size)
+    }
+
+    pub fn is_source(&self, size: f64) -> bool {
+        self.source.matches(size)
+    }
+
+    pub fn is_glyph(&self, size: f64) -> bool {
+        size > self.min_glyph_size && !self.is_code(size) && !self.is_source(size)
+    }
+}
+
+impl Default for LayoutProfile {
+    fn default() -> Self {
+        Self::unicode15()
+    }
+}
+
+impl fmt::Display for LayoutProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl FromStr for LayoutProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(profile) = Self::builtin(s) {
+            return Ok(profile);
+        }
+        if !Path::new(s).exists() {
+            return Err(format!(
+                "未知的版式: {}，可選: {}，或版式文件的路徑",
+                s,
+                Self::BUILTIN.join(", ")
+            ));
+        }
+
+        Self::load(s).map_err(|err| err.to_string())
+    }
+}
+
+/// 字符的字號，取變換矩陣的橫向縮放
+pub fn font_size(matrix: &[f64]) -> f64 {
+    matrix[0]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        calibrate::{calibrate, SAMPLE_PAGES},
+        page_source::MemoryPageSource,
+    };
+
+    #[test]
+    fn load_profiles() {
+        let dir = crate::test_dir("layout");
+
+        // 未填的項目取 unicode-15 的值，名稱取文件名
+        let path = dir.join("old-charts.toml");
+        fs::write(
+            &path,
+            "row_tolerance = 6.0\n[code]\nsize = 10.0\ntolerance = 0.1\n",
+        )
+        .unwrap();
+        let profile = LayoutProfile::load(&path).unwrap();
+        assert_eq!(profile.name, "old-charts");
+        assert_eq!(profile.code, SizeRule::new(10.0, 0.1));
+        assert_eq!(profile.row_tolerance, 6.0);
+        assert_eq!(profile.source, LayoutProfile::unicode15().source);
+
+        // 明確填寫的名稱保持不變，即使與內置版式同名
+        let path = dir.join("named.toml");
+        fs::write(&path, "name = \"unicode-15\"\nmin_glyph_size = 7.0\n").unwrap();
+        let profile = LayoutProfile::load(&path).unwrap();
+        assert_eq!(profile.name, "unicode-15");
+        assert_eq!(profile.min_glyph_size, 7.0);
+
+        assert_eq!(LayoutProfile::resolve("named.toml", &dir).unwrap(), profile);
+        assert_eq!(
+            "Unicode-15".parse::<LayoutProfile>().unwrap(),
+            LayoutProfile::unicode15()
+        );
+        assert!("unicode-14".parse::<LayoutProfile>().is_err());
+    }
+
+    /// 一代圖表的排版：字號、字距與格子大小
+    struct ChartStyle {
+        code_size: f64,
+        /// 同一格內各編碼字符的字號，模擬字號的細微出入
+        code_jitter: f64,
+        code_advance: f64,
+        source_size: f64,
+        source_advance: f64,
+        glyph_size: f64,
+        /// 同一碼位相鄰字形的橫向距離
+        glyph_pitch: f64,
+        /// 字形基線、字源基線相對編碼基線的位置
+        glyph_drop: f64,
+        source_drop: f64,
+        cell_width: f64,
+        cell_height: f64,
+    }
+
+    const SOURCES: [&str; 3] = ["G0-523B", "T1-4421", "J0-306C"];
+
+    // 2 × 2 格，每格一個碼位並列三個字源，列優先排列
+    fn chart_page(style: &ChartStyle) -> String {
+        let mut uses = vec![];
+        let mut push = |text: String, font: &str, size: f64, x: f64, y: f64| {
+            uses.push(format!(
+                r##"<use data-text="{}" xlink:href="#{}" transform="matrix({},0,0,-{},{},{})"/>"##,
+                text, font, size, size, x, y
+            ))
+        };
+        for idx in 0..4u32 {
+            let codepoint = 0x4E00 + idx;
+            let x = 50.0 + (idx / 2) as f64 * style.cell_width;
+            let y = 100.0 + (idx % 2) as f64 * style.cell_height;
+            let code_size = style.code_size + style.code_jitter * idx as f64;
+            for (i, ch) in format!("{:04X}", codepoint).chars().enumerate() {
+                let x = x + style.code_advance * i as f64;
+                push(ch.to_string(), "label", code_size, x, y);
+            }
+            for (k, source) in SOURCES.iter().enumerate() {
+                let x = x + style.cell_width / 3.0 + style.glyph_pitch * k as f64;
+                let text = format!("&#x{:x};", codepoint);
+                push(text, "glyph", style.glyph_size, x, y + style.glyph_drop);
+                for (i, ch) in source.chars().enumerate() {
+                    let x = x + style.source_advance * i as f64;
+                    push(
+                        ch.to_string(),
+                        "label",
+                        style.source_size,
+                        x,
+                        y + style.source_drop,
+                    );
+                }
+            }
+        }
+
+        format!(
+            r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink"><defs><path id="glyph" d="M0 .5L1 .5"/><path id="label" d="M0 0L.5 0"/></defs><g>{}</g></svg>"##,
+            uses.concat()
+        )
+    }
+
+    // 每個碼位都應得到三個字源
+    fn parses_chart(page_svg: &str, layout: &LayoutProfile) -> bool {
+        let Ok(page) = crate::chart::parse_page(page_svg, 1, &(0x4E00..=0x9FFF), layout) else {
+            return false;
+        };
+        page.unpaired.is_empty()
+            && page.entries.len() == 4
+            && page.entries.iter().all(|entry| {
+                let mut sources = entry
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.source.to_string())
+                    .collect::<Vec<_>>();
+                sources.sort();
+                sources == ["G0-523B", "J0-306C", "T1-4421"]
+            })
+    }
+
+    #[test]
+    fn builtin_layouts() {
+        let unicode15 = ChartStyle {
+            code_size: 9.9998,
+            code_jitter: 0.0,
+            code_advance: 6.0,
+            source_size: 6.0,
+            source_advance: 3.5,
+            glyph_size: 22.0,
+            glyph_pitch: 30.0,
+            glyph_drop: 30.0,
+            source_drop: 45.0,
+            cell_width: 150.0,
+            cell_height: 60.0,
+        };
+        // 標籤字號各有出入
+        let legacy = ChartStyle {
+            code_jitter: 0.03,
+            source_size: 6.2,
+            ..unicode15
+        };
+        // 標籤與字形都較小，格子較密
+        let iso10646 = ChartStyle {
+            code_size: 8.0,
+            code_jitter: 0.0,
+            code_advance: 4.8,
+            source_size: 5.0,
+            source_advance: 3.0,
+            glyph_size: 16.0,
+            glyph_pitch: 26.0,
+            glyph_drop: 24.0,
+            source_drop: 34.0,
+            cell_width: 130.0,
+            cell_height: 45.0,
+        };
+
+        let pages = [
+            (LayoutProfile::unicode15(), chart_page(&unicode15)),
+            (LayoutProfile::legacy(), chart_page(&legacy)),
+            (LayoutProfile::iso10646(), chart_page(&iso10646)),
+        ];
+        for (layout, page_svg) in &pages {
+            assert_eq!(&LayoutProfile::builtin(&layout.name).unwrap(), layout);
+            assert!(parses_chart(page_svg, layout), "{}", layout);
+
+            // 自動校準得出的版式同樣適用
+            let doc = MemoryPageSource::new(["<svg><defs/></svg>".to_string(), page_svg.clone()]);
+            let calibration = calibrate(&doc, SAMPLE_PAGES).unwrap();
+            assert!(
+                parses_chart(page_svg, &calibration.profile),
+                "{}",
+                calibration
+            );
+        }
+        // 其他兩代圖表的標籤不合 unicode-15 的字號
+        assert!(!parses_chart(&pages[1].1, &pages[0].0));
+        assert!(!parses_chart(&pages[2].1, &pages[0].0));
+    }
+}
@@ -1,171 +1,309 @@
-use itertools::Itertools;
-use pyo3::{
-    types::{IntoPyDict, PyDict},
-    PyResult, Python,
+use std::{
+    fs,
+    ops::{Bound, RangeBounds, RangeInclusive},
+    path::Path,
+    str::FromStr,
 };
-use std::{io::Cursor, ops::RangeBounds, path::Path};
-
-use image_gen::svg_drawn_to_image;
-use parse::*;
-use utils::*;
-use xlsx::Xlsx;
 
+use blocks::{from_file_name, Block};
+use calibrate::layout_or_calibrate;
+use chart::{page_codepoints, parse_page, ChartPage};
+use detect::{chart_metadata, detect_chart, resolve_codepoints, ChartMetadata, Detection};
+use image_gen::RenderOptions;
+use layout::LayoutProfile;
+use output::{write_output, ColumnLayout, EntryOrder, OutputFormat};
+use page_source::{open_page_source, page_file_name, PageSource};
+use report::Report;
+
+pub use error::{Error, Result};
+
+pub mod blocks;
+pub mod calibrate;
+pub mod chart;
+pub mod detect;
+pub mod error;
+pub mod font;
+pub mod grid;
 pub mod image_gen;
+pub mod job;
+pub mod layout;
+pub mod outline;
+pub mod output;
+pub mod page_source;
 pub mod parse;
+pub mod pdf;
+#[cfg(feature = "pymupdf")]
+pub mod pymupdf;
+pub mod report;
+pub mod source;
 pub mod utils;
 pub mod xlsx;
 
-pub fn run<R1: RangeBounds<usize> + Iterator<Item = usize>, R2: RangeBounds<u32>>(
-    input: &str,
-    output: &str,
-    page_range: R1,
-    codepoint_range: R2,
-    limit: u32,
-) -> PyResult<()> {
-    let mut xlsx = Xlsx::new();
-
-    Python::with_gil(|py| {
-        let fitz = py.import("fitz")?;
-
-        let doc = fitz.call_method1("open", (input,))?;
-        let fitz_matrix_identity = (1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
-
-        let pdf_len: usize = doc.call_method0("__len__")?.extract()?; // 首頁爲說明頁
-
-        let mut page_count = 1;
-        let page_range = page_range_normalize(page_range, 1, pdf_len - 1);
-        let mut page_range_peek = page_range.peekable();
-        while let Some(page_idx) = page_range_peek.next() {
-            let page = doc.call_method1("__getitem__", (page_idx,))?;
-            let page_svg: String = page
-                .call_method(
-                    "get_svg_image",
-                    (),
-                    Some(vec![("matrix", &fitz_matrix_identity)].into_py_dict(py)),
-                )?
-                .extract()?;
-
-            // 初步解析
-            let font_dic = parse_font_drawn(&page_svg);
-            let detail_list = parse_details(&page_svg); // character, font-name, matrix
-
-            // 結構化
-            let mut source_vec = ChartSource::new(&detail_list, 7.0);
-            let mut graph_vec = ChartGraph::new(&detail_list, &font_dic, &codepoint_range);
-            let mut code_vec = ChartCode::new(&detail_list, 10.0); // 整頁的code_vec
-
-            // 排序
-            source_vec.sort_y_x(5.0);
-            graph_vec.sort_y_x(5.0);
-
-            // 按行分割
-            let source_rows = split_chart_source(&source_vec, 10.0);
-            let graph_rows = split_chart_graph(&graph_vec, 10.0);
-
-            if !(source_vec.len() == graph_vec.len() && source_rows.len() == graph_rows.len()) {
-                panic!("解析文件 {} 的第 {} 頁時發生了錯誤...", input, page_idx);
-            }
+/// 讀取 PDF 的後端
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Native,
+    #[cfg(feature = "pymupdf")]
+    PyMuPdf,
+}
 
-            // 單列的code_vec
-            let mut code_vec_split = split_code_vec(&mut code_vec);
-            // 依照編碼的位置將 (字形, 字源) 按列分組
-            let row_ranges = get_row_range_from_code_vec(&mut code_vec);
-            let cols =
-                group_graph_source_iter_by_col(graph_vec.iter().zip(&source_vec), &row_ranges);
+impl FromStr for Backend {
+    type Err = String;
 
-            let col_max = 7;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "native" => Ok(Backend::Native),
+            #[cfg(feature = "pymupdf")]
+            "pymupdf" => Ok(Backend::PyMuPdf),
+            _ => Err(format!("不支持的後端: {}", s)),
+        }
+    }
+}
 
-            for (code_vec_each, col_each) in code_vec_split.iter_mut().zip(cols.into_iter()) {
-                // 將每列的 (字形, 字源) 再按行分組
-                let col_ranges = get_col_range_from_code_vec(code_vec_each);
-                let elements = group_graph_source_iter_by_row(col_each.into_iter(), &col_ranges);
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    pub pages: (Bound<usize>, Bound<usize>),
+    // 未指定時從圖表中識別
+    pub codepoints: Option<RangeInclusive<u32>>,
+    // 每個工作表包含的頁數
+    pub limit: u32,
+    pub format: OutputFormat,
+    pub backend: Backend,
+    // 寬鬆模式：跳過出錯的頁面與字形，並在輸出旁保存報告
+    pub lenient: bool,
+    // 未指定時依前幾頁自動校準
+    pub layout: Option<LayoutProfile>,
+    pub order: EntryOrder,
+    // xlsx 中字形的分列方式
+    pub columns: ColumnLayout,
+    // xlsx 中字形圖片的尺寸與樣式
+    pub render: RenderOptions,
+    // SVG 輸出中附上碼位、字源與頁碼
+    pub svg_metadata: bool,
+}
 
-                for (code, graph_source) in code_vec_each.iter().zip(elements.into_iter()) {
-                    // println!("{}", code.hex());
+impl ConvertOptions {
+    pub fn new(codepoints: Option<RangeInclusive<u32>>) -> Self {
+        Self {
+            pages: (Bound::Unbounded, Bound::Unbounded),
+            codepoints,
+            limit: 100,
+            format: OutputFormat::default(),
+            backend: Backend::default(),
+            lenient: false,
+            layout: None,
+            order: EntryOrder::default(),
+            columns: ColumnLayout::default(),
+            render: RenderOptions::default(),
+            svg_metadata: false,
+        }
+    }
+}
 
-                    let images_with_source = graph_source
-                        .into_iter()
-                        .map(|(graph, source)| {
-                            let img = svg_drawn_to_image(graph.drawn);
+/// 一次轉換的統計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RunSummary {
+    pub pages: usize,
+    pub entries: usize,
+    pub glyphs: usize,
+    pub problems: usize,
+}
 
-                            (source.source.clone(), img)
-                        })
-                        .collect_vec();
+pub fn run(input: &str, output: &str, options: &ConvertOptions) -> Result<RunSummary> {
+    let doc = open_page_source(input, options.backend)?;
+
+    // 圖表中無法識別範圍時，依文件名推斷，如 `U20000.pdf`
+    let mut options = options.clone();
+    let mut report = Report::default();
+    let layout = layout_or_calibrate(doc.as_ref(), options.layout.as_ref(), &mut report);
+    let cover = chart_metadata(doc.as_ref())?;
+    let detection = detect(doc.as_ref(), &cover, &options, &layout, &mut report)?;
+    if options.codepoints.is_none() && detection.range().is_none() {
+        options.codepoints = from_file_name(input).map(Block::range);
+    }
 
-                    xlsx.add_row(&code.hex(), &images_with_source, col_max);
-                }
-            }
+    convert(
+        doc.as_ref(),
+        output,
+        &options,
+        &layout,
+        &detection,
+        &cover,
+        report,
+    )
+}
 
-            println!("第 {:03} 頁已處理", page_idx);
+pub fn run_source(
+    doc: &dyn PageSource,
+    output: &str,
+    options: &ConvertOptions,
+) -> Result<RunSummary> {
+    let mut report = Report::default();
+    let layout = layout_or_calibrate(doc, options.layout.as_ref(), &mut report);
+    let cover = chart_metadata(doc)?;
+    let detection = detect(doc, &cover, options, &layout, &mut report)?;
+
+    convert(doc, output, options, &layout, &detection, &cover, report)
+}
 
-            if page_count % limit == 0 && page_range_peek.peek().is_some() {
-                xlsx.next_sheet();
-            }
-            page_count += 1;
+/// 寬鬆模式下識別失敗時記入 `report`，當作未能識別
+pub fn detect(
+    doc: &dyn PageSource,
+    cover: &ChartMetadata,
+    options: &ConvertOptions,
+    layout: &LayoutProfile,
+    report: &mut Report,
+) -> Result<Detection> {
+    match detect_chart(doc, cover, layout) {
+        Ok(detection) => Ok(detection),
+        Err(err) if options.lenient => {
+            report.warn(err.page(), vec![], format!("無法識別圖表: {}", err));
+            Ok(Detection::default())
         }
+        Err(err) => Err(err),
+    }
+}
 
-        // xlsx.save(output);
-        // Ok(())
+fn convert(
+    doc: &dyn PageSource,
+    output: &str,
+    options: &ConvertOptions,
+    layout: &LayoutProfile,
+    detection: &Detection,
+    metadata: &ChartMetadata,
+    mut report: Report,
+) -> Result<RunSummary> {
+    let pages = parse_pages(doc, options, layout, detection, &mut report)?;
+    write_output(&pages, metadata, output, options, &mut report)?;
+    // 非寬鬆模式下報告中只有超出圖片範圍的字形
+    if options.lenient || !report.is_empty() {
+        report.save(output)?;
+    }
 
-        let zip_buf = xlsx.save_to_buffer();
-        match rezip(&py, &zip_buf, output) {
-            Ok(()) => Ok(()),
-            Err(err) => Err(err),
-        }
+    let entries = pages.iter().flat_map(|page| &page.entries);
+    Ok(RunSummary {
+        pages: pages.len(),
+        entries: entries.clone().count(),
+        glyphs: entries.map(|entry| entry.glyphs.len()).sum(),
+        problems: report.len(),
     })
 }
 
-fn rezip<P: AsRef<Path>>(py: &pyo3::Python, zip_buf: &Vec<u8>, path: P) -> PyResult<()> {
-    let zipfile_class = py.import("zipfile").unwrap().getattr("ZipFile")?;
-    let kwargs = PyDict::new(*py);
-    kwargs.set_item("mode", "w")?;
-    kwargs.set_item("compression", 8)?;
-    kwargs.set_item("compresslevel", 6)?;
-    let zipfile = zipfile_class
-        .call((path.as_ref().to_str().unwrap(),), Some(kwargs))
-        .unwrap();
-
-    let reader = Cursor::new(zip_buf);
-    let mut zip_archive = zip::ZipArchive::new(reader).unwrap();
-
-    let mut buf_writer = vec![];
-
-    for i in 0..zip_archive.len() {
-        let mut file = zip_archive.by_index(i).unwrap();
-        let filepath = match file.enclosed_name() {
-            Some(path) => path.to_owned(),
-            None => continue,
+/// 寬鬆模式下出錯的頁面只保留碼位，問題記入 `report`；否則遇錯即返回
+pub fn parse_pages(
+    doc: &dyn PageSource,
+    options: &ConvertOptions,
+    layout: &LayoutProfile,
+    detection: &Detection,
+    report: &mut Report,
+) -> Result<Vec<ChartPage>> {
+    let pdf_len = doc.page_count(); // 首頁爲說明頁
+    let codepoints = resolve_codepoints(detection, options.codepoints.clone())?;
+
+    let mut pages = vec![];
+    for page_idx in page_range_normalize(options.pages, 1, pdf_len) {
+        let page_svg = match doc.page_svg(page_idx) {
+            Ok(page_svg) => page_svg,
+            Err(err) if options.lenient => {
+                report.push(Some(page_idx), vec![], &err);
+                continue;
+            }
+            Err(err) => return Err(err.at_page(page_idx)),
         };
 
-        std::io::copy(&mut file, &mut buf_writer).unwrap();
-
-        zipfile
-            .call_method1("writestr", (filepath.to_str().unwrap(), &buf_writer[..]))
-            .unwrap();
+        match parse_page(&page_svg, page_idx, &codepoints, layout) {
+            Ok(page) => {
+                for unpaired in &page.unpaired {
+                    let err = unpaired.to_error();
+                    if options.lenient {
+                        report.push(Some(page_idx), unpaired.codepoints(), &err);
+                    } else {
+                        eprintln!("警告: 第 {} 頁: {}", page_idx, err);
+                    }
+                }
+                pages.push(page);
+            }
+            Err(err) if options.lenient => {
+                let codes = page_codepoints(&page_svg, layout);
+                report.push(
+                    Some(page_idx),
+                    codes.iter().map(|&(codepoint, _)| codepoint).collect(),
+                    &err,
+                );
+                pages.push(ChartPage::without_glyphs(page_idx, codes));
+            }
+            Err(err) => return Err(err),
+        }
 
-        buf_writer.clear();
+        println!("第 {:03} 頁已處理", page_idx);
     }
 
-    zipfile.call_method0("close").unwrap();
+    Ok(pages)
+}
 
-    Ok(())
+/// 將頁面 SVG 導出爲 `page-NNN.svg`，可供 `DirPageSource` 讀取
+pub fn export_pages<R: RangeBounds<usize>, P: AsRef<Path>>(
+    doc: &dyn PageSource,
+    dir: P,
+    page_range: R,
+) -> Result<usize> {
+    fs::create_dir_all(&dir).map_err(|err| Error::file(dir.as_ref(), err))?;
+
+    let page_range = page_range_normalize(page_range, 0, doc.page_count());
+    let mut count = 0;
+    for page_idx in page_range {
+        let page_svg = doc
+            .page_svg(page_idx)
+            .map_err(|err| err.at_page(page_idx))?;
+        let path = dir.as_ref().join(page_file_name(page_idx));
+        fs::write(&path, page_svg).map_err(|err| Error::file(path, err))?;
+        count += 1;
+    }
+
+    Ok(count)
 }
 
-fn page_range_normalize<R: RangeBounds<usize> + Iterator<Item = usize>>(
+/// 將頁碼範圍限制在 `min` 至最後一頁之間，未指定起點時從 `min` 開始
+pub fn page_range_normalize<R: RangeBounds<usize>>(
     page_range: R,
     min: usize,
-    max: usize,
+    page_count: usize,
 ) -> std::ops::Range<usize> {
     let start = match page_range.start_bound() {
-        std::ops::Bound::Included(&num) => num,
-        std::ops::Bound::Excluded(_) => min,
-        std::ops::Bound::Unbounded => min,
+        Bound::Included(&num) => num,
+        Bound::Excluded(&num) => num.saturating_add(1),
+        Bound::Unbounded => min,
     };
     let end = match page_range.end_bound() {
-        std::ops::Bound::Included(&num) => num + 1,
-        std::ops::Bound::Excluded(&num) => num,
-        std::ops::Bound::Unbounded => max + 1,
+        Bound::Included(&num) => num.saturating_add(1),
+        Bound::Excluded(&num) => num,
+        Bound::Unbounded => page_count,
     };
 
-    start..end
+    start..end.min(page_count)
+}
+
+/// 測試專用的空目錄，以進程號區分，並行運行的測試互不干擾
+#[cfg(test)]
+pub(crate) fn test_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("cjk-unicode-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_page_ranges() {
+        let unbounded = (Bound::<usize>::Unbounded, Bound::Unbounded);
+        assert_eq!(page_range_normalize(unbounded, 1, 10), 1..10);
+        assert_eq!(page_range_normalize(unbounded, 0, 0), 0..0);
+        assert_eq!(page_range_normalize(3..=20, 1, 10), 3..10);
+        let excluded = (Bound::Excluded(3), Bound::Excluded(6));
+        assert_eq!(page_range_normalize(excluded, 1, 10), 4..6);
+    }
 }
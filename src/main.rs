@@ -1,23 +1,498 @@
-use cjk_unicode_pdf_to_xlsx::run;
-
-fn main() {
-    let infos = vec![
-        ("U4E00.pdf", "basic.xlsx", 0x4e00..=0x9fff),
-        // ("U3400.pdf", "ext-a.xlsx", 0x3400..=0x4DBF),
-        ("U20000.pdf", "ext-b.xlsx", 0x20000..=0x2A6DF),
-        // ("U2A700.pdf", "ext-c.xlsx", 0x2A700..=0x2B739),
-        // ("U2B740.pdf", "ext-d.xlsx", 0x2B740..=0x2B81D),
-        // ("U2B820.pdf", "ext-e.xlsx", 0x2B820..=0x2CEA1),
-        // ("U2CEB0.pdf", "ext-f.xlsx", 0x2CEB0..=0x2EBE0),
-        // ("U30000.pdf", "ext-g.xlsx", 0x30000..=0x3134A),
-        // ("U31350.pdf", "ext-h.xlsx", 0x31350..=0x323AF),
-    ];
-
-    for (input_name, output_name, codepoint_range) in infos {
-        let input_path = format!("./cjk-unicode-pdf/{}", input_name);
-        let output_path = format!("./result/{}", output_name);
-        println!("正在處理文件 {} 中:", input_path);
-        run(&input_path, &output_path, 1.., codepoint_range, 100).unwrap();
-        println!();
+use std::{
+    error::Error,
+    ops::{Bound, RangeInclusive},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use cjk_unicode_pdf_to_xlsx::{
+    blocks::{blocks_in, from_file_name, parse_block, Block, UnicodeVersion},
+    calibrate::layout_or_calibrate,
+    chart::{page_stats, parse_page},
+    detect::{chart_metadata, detect_chart, resolve_codepoints, Detection},
+    export_pages,
+    image_gen::{Centering, Color, Overflow, RenderOptions},
+    job::{print_summaries, JobFile},
+    layout::LayoutProfile,
+    output::{ColumnLayout, EntryOrder, OutputFormat},
+    page_range_normalize,
+    page_source::open_page_source,
+    report::Report,
+    run,
+    utils::{parse_codepoint_range, parse_page_range},
+    Backend, ConvertOptions,
+};
+use clap::{Args, Parser, Subcommand};
+
+/// 將 Unicode 中日韓統一表意文字圖表轉換爲 xlsx 等格式
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// 轉換圖表文件
+    Convert(ConvertArgs),
+    /// 顯示文件信息及每頁的元素數量
+    Inspect(InspectArgs),
+    /// 將頁面導出爲 page-NNN.svg
+    Export(ExportArgs),
+    /// 檢查每頁能否正確解析，不寫出結果
+    Validate(InspectArgs),
+    /// 依 TOML 任務清單批量轉換
+    Batch(BatchArgs),
+    /// 列出已知的中日韓區塊
+    Blocks(BlocksArgs),
+}
+
+#[derive(Args)]
+struct SourceArgs {
+    /// 輸入的 PDF，或包含 page-NNN.svg 的目錄
+    input: String,
+
+    /// 頁碼範圍，如 `1-10`、`3-`，首頁（第 0 頁）爲說明頁
+    #[arg(short, long, value_parser = parse_page_range)]
+    pages: Option<(Bound<usize>, Bound<usize>)>,
+
+    /// 讀取 PDF 的後端
+    #[arg(long, default_value = "native")]
+    backend: Backend,
+
+    /// 頁面版式：unicode-15、legacy、iso10646，或版式文件（TOML）的路徑，默認依前幾頁自動校準
+    #[arg(long)]
+    layout: Option<LayoutProfile>,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// 輸出文件，默認與輸入同名
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    range: RangeArgs,
+
+    /// 每個工作表包含的頁數
+    #[arg(short, long, default_value_t = 100)]
+    limit: u32,
+
+    /// 輸出格式：xlsx、json、csv，或 svg、ttf（輸出爲目錄），默認依輸出文件的擴展名決定
+    #[arg(short, long)]
+    format: Option<OutputFormat>,
+
+    /// 跳過出錯的頁面與字形，並在輸出旁保存 .report.json 與 .report.txt
+    #[arg(long)]
+    lenient: bool,
+
+    /// 碼位的次序：codepoint，或 dictionary（按字源所引用字典的卷、頁排列）
+    #[arg(long, default_value = "codepoint")]
+    order: EntryOrder,
+
+    /// xlsx 中字形的分列方式：sequential 依次排列，region 每個地區固定一列並附表頭
+    #[arg(long, default_value = "sequential")]
+    columns: ColumnLayout,
+
+    /// SVG 輸出中附上碼位、字源與頁碼
+    #[arg(long)]
+    svg_metadata: bool,
+
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+/// xlsx 中字形圖片的尺寸與樣式，未指定的項目取默認值
+#[derive(Args)]
+struct RenderArgs {
+    /// 字形圖片的邊長（像素），默認 128
+    #[arg(long)]
+    size: Option<u32>,
+
+    /// 一個 em 佔留白以內區域的比例，默認 0.859375
+    #[arg(long)]
+    em_scale: Option<f64>,
+
+    /// 四周留白（像素），默認 0
+    #[arg(long)]
+    padding: Option<u32>,
+
+    /// 字形的擺放方式：bounding-box 外框居中，horizontal 僅橫向居中，em-box 保留字形在 em 框中的位置
+    #[arg(long)]
+    centering: Option<Centering>,
+
+    /// em 框底邊的縱坐標，默認 0，字形基線在表意字框底邊之上時可設爲 -0.12 等
+    #[arg(long, allow_hyphen_values = true)]
+    em_bottom: Option<f64>,
+
+    /// 字形超出圖片時的處理方式：fit 縮小至留白以內（默認），clip 裁去超出部分，enlarge 擴大畫布
+    #[arg(long)]
+    overflow: Option<Overflow>,
+
+    /// 背景顏色，如 `#FFFFFF`
+    #[arg(long)]
+    background: Option<Color>,
+
+    /// 字形顏色，如 `#000000`
+    #[arg(long)]
+    foreground: Option<Color>,
+}
+
+impl RenderArgs {
+    fn options(&self) -> RenderOptions {
+        let default = RenderOptions::default();
+        RenderOptions {
+            size: self.size.unwrap_or(default.size),
+            em_scale: self.em_scale.unwrap_or(default.em_scale),
+            padding: self.padding.unwrap_or(default.padding),
+            centering: self.centering.unwrap_or(default.centering),
+            em_bottom: self.em_bottom.unwrap_or(default.em_bottom),
+            overflow: self.overflow.unwrap_or(default.overflow),
+            background: self.background.unwrap_or(default.background),
+            foreground: self.foreground.unwrap_or(default.foreground),
+        }
+    }
+}
+
+#[derive(Args)]
+struct InspectArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[command(flatten)]
+    range: RangeArgs,
+}
+
+/// 碼位範圍，未指定時依輸入文件名推斷，如 `U20000.pdf`
+#[derive(Args)]
+struct RangeArgs {
+    /// 碼位範圍（十六進制），如 `4E00-9FFF`
+    #[arg(short, long, value_parser = parse_codepoint_range)]
+    codepoints: Option<RangeInclusive<u32>>,
+
+    /// 區塊名稱，如 `basic`、`ext-b`，可用 `blocks` 子命令查看
+    #[arg(short, long, value_parser = parse_block, conflicts_with = "codepoints")]
+    block: Option<&'static Block>,
+}
+
+impl RangeArgs {
+    fn requested(&self) -> Option<RangeInclusive<u32>> {
+        self.codepoints
+            .clone()
+            .or_else(|| self.block.map(Block::range))
+    }
+
+    /// 與 `run` 相同，圖表中無法識別範圍時依文件名推斷
+    fn resolve(
+        &self,
+        detection: &Detection,
+        input: &str,
+    ) -> Result<RangeInclusive<u32>, Box<dyn Error>> {
+        let requested = self.requested();
+        if requested.is_none() && detection.range().is_none() {
+            if let Some(block) = from_file_name(input) {
+                return Ok(block.range());
+            }
+        }
+
+        Ok(resolve_codepoints(detection, requested)?)
+    }
+}
+
+#[derive(Args)]
+struct BlocksArgs {
+    /// 只列出該 Unicode 版本已有的區塊，並顯示其已分配範圍
+    #[arg(short, long, default_value_t = UnicodeVersion::LATEST)]
+    unicode: UnicodeVersion,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// 輸出目錄
+    #[arg(short, long)]
+    output: PathBuf,
+}
+
+#[derive(Args)]
+struct BatchArgs {
+    /// 任務清單，相對路徑以清單所在目錄爲起點
+    jobs: PathBuf,
+
+    /// 只執行指定名稱的任務
+    #[arg(long)]
+    only: Vec<String>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let res = match cli.command {
+        Command::Convert(args) => convert(args),
+        Command::Inspect(args) => inspect(args),
+        Command::Export(args) => export(args),
+        Command::Validate(args) => validate(args),
+        Command::Batch(args) => batch(args),
+        Command::Blocks(args) => blocks(args),
+    };
+
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("錯誤: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn pages_or_all(pages: Option<(Bound<usize>, Bound<usize>)>) -> (Bound<usize>, Bound<usize>) {
+    pages.unwrap_or((Bound::Unbounded, Bound::Unbounded))
+}
+
+fn convert(args: ConvertArgs) -> Result<bool, Box<dyn Error>> {
+    let format = args
+        .format
+        .or_else(|| args.output.as_ref().and_then(OutputFormat::from_path))
+        .unwrap_or_default();
+    let output = args
+        .output
+        .unwrap_or_else(|| format.default_path(&args.source.input));
+
+    let mut options = ConvertOptions::new(args.range.requested());
+    options.pages = pages_or_all(args.source.pages);
+    options.limit = args.limit;
+    options.format = format;
+    options.backend = args.source.backend;
+    options.layout = args.source.layout;
+    options.lenient = args.lenient;
+    options.order = args.order;
+    options.columns = args.columns;
+    options.render = args.render.options();
+    options.svg_metadata = args.svg_metadata;
+
+    println!("正在處理文件 {} 中:", args.source.input);
+    let summary = run(&args.source.input, &output.to_string_lossy(), &options)?;
+    println!("已寫入 {}", output.display());
+    if args.lenient || summary.problems > 0 {
+        let (_, text_path) = Report::paths(&output);
+        println!(
+            "共 {} 個問題，詳見 {}",
+            summary.problems,
+            text_path.display()
+        );
+    }
+
+    Ok(true)
+}
+
+fn inspect(args: InspectArgs) -> Result<bool, Box<dyn Error>> {
+    let doc = open_page_source(&args.source.input, args.source.backend)?;
+    // 此處不保存報告，校準失敗只打印警告
+    let layout = &layout_or_calibrate(
+        doc.as_ref(),
+        args.source.layout.as_ref(),
+        &mut Report::default(),
+    );
+    let chart = chart_metadata(doc.as_ref())?;
+    let detection = detect_chart(doc.as_ref(), &chart, layout)?;
+    let requested = args.range.requested();
+    if let Some(Err(err)) = requested.as_ref().map(|range| detection.check(range)) {
+        println!("警告: {}", err);
+    }
+    // 區塊登記表已覆蓋所有中日韓字形，此處的範圍僅作補充
+    let codepoints = requested.or_else(|| detection.range()).unwrap_or(0..=0);
+
+    println!("頁數: {}", doc.page_count());
+    let metadata = doc.metadata();
+    for (name, value) in [
+        ("標題", metadata.title),
+        ("作者", metadata.author),
+        ("主題", metadata.subject),
+        ("創建工具", metadata.creator),
+        ("生成工具", metadata.producer),
+        ("創建日期", metadata.creation_date),
+        ("修改日期", metadata.modification_date),
+    ] {
+        if let Some(value) = value {
+            println!("{}: {}", name, value);
+        }
+    }
+    if let Some(block) = detection.block {
+        println!("區塊: {}", block);
+    }
+    if let Some(version) = chart.unicode_version {
+        println!("Unicode 版本: {}", version);
+    }
+    if let Some(date) = chart.chart_date {
+        println!("圖表日期: {}", date);
+    }
+    for (name, range) in [
+        ("首頁範圍", detection.cover_range),
+        ("編碼範圍", detection.code_range),
+    ] {
+        if let Some(range) = range {
+            println!("{}: {:04X}-{:04X}", name, range.start(), range.end());
+        }
+    }
+
+    println!("頁碼\t編碼\t字源\t字形\t網格\t碼位範圍");
+    let pages = pages_or_all(args.source.pages);
+    for page_idx in page_range_normalize(pages, 0, doc.page_count()) {
+        let stats = match doc
+            .page_svg(page_idx)
+            .and_then(|page_svg| page_stats(&page_svg, &codepoints, layout))
+        {
+            Ok(stats) => stats,
+            Err(err) => {
+                println!("{:03}\t{}", page_idx, err);
+                continue;
+            }
+        };
+        let span = match (stats.first_codepoint, stats.last_codepoint) {
+            (Some(first), Some(last)) => format!("{:X}-{:X}", first, last),
+            _ => "-".to_string(),
+        };
+        println!(
+            "{:03}\t{}\t{}\t{}\t{}×{}\t{}",
+            page_idx, stats.codes, stats.sources, stats.graphs, stats.columns, stats.rows, span
+        );
+    }
+
+    Ok(true)
+}
+
+fn export(args: ExportArgs) -> Result<bool, Box<dyn Error>> {
+    let doc = open_page_source(&args.source.input, args.source.backend)?;
+    let count = export_pages(doc.as_ref(), &args.output, pages_or_all(args.source.pages))?;
+    println!("已導出 {} 頁至 {}", count, args.output.display());
+
+    Ok(true)
+}
+
+fn validate(args: InspectArgs) -> Result<bool, Box<dyn Error>> {
+    let doc = open_page_source(&args.source.input, args.source.backend)?;
+    // 此處不保存報告，校準失敗只打印警告
+    let layout = &layout_or_calibrate(
+        doc.as_ref(),
+        args.source.layout.as_ref(),
+        &mut Report::default(),
+    );
+    let cover = chart_metadata(doc.as_ref())?;
+    let detection = detect_chart(doc.as_ref(), &cover, layout)?;
+    let codepoints = args.range.resolve(&detection, &args.source.input)?;
+
+    let mut failed = 0;
+    let mut checked = 0;
+    // 未指定頁碼時跳過首頁
+    let pages = pages_or_all(args.source.pages);
+    for page_idx in page_range_normalize(pages, 1, doc.page_count()) {
+        checked += 1;
+        let page = doc
+            .page_svg(page_idx)
+            .map_err(|err| err.at_page(page_idx))
+            .and_then(|page_svg| parse_page(&page_svg, page_idx, &codepoints, layout));
+        match page {
+            Ok(page) => {
+                for unpaired in &page.unpaired {
+                    println!("警告: 第 {} 頁: {}", page_idx, unpaired.to_error());
+                }
+            }
+            Err(err) => {
+                println!("{}", err);
+                failed += 1;
+            }
+        }
+    }
+    println!("共檢查 {} 頁，{} 頁有誤", checked, failed);
+
+    Ok(failed == 0)
+}
+
+fn batch(args: BatchArgs) -> Result<bool, Box<dyn Error>> {
+    let mut job_file = JobFile::load(&args.jobs)?;
+    if !args.only.is_empty() {
+        job_file.jobs.retain(|job| args.only.contains(&job.name()));
+    }
+
+    let base_dir = args.jobs.parent().unwrap_or(Path::new("."));
+    let summaries = job_file.run_all(base_dir);
+    print_summaries(&summaries);
+
+    Ok(summaries.iter().all(|summary| summary.result.is_ok()))
+}
+
+fn blocks(args: BlocksArgs) -> Result<bool, Box<dyn Error>> {
+    println!("名稱\t區塊範圍\t已分配\t始於\t區塊");
+    for block in blocks_in(args.unicode) {
+        let assigned = match block.assigned(args.unicode) {
+            Some(range) => format!("{:04X}-{:04X}", range.start(), range.end()),
+            None => "-".to_string(),
+        };
+        println!(
+            "{}\t{:04X}-{:04X}\t{}\t{}\t{}",
+            block.id,
+            block.start,
+            block.end,
+            assigned,
+            block.since(),
+            block.name
+        );
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn render_args() {
+        let cli = Cli::try_parse_from([
+            "cjk-unicode-pdf-to-xlsx",
+            "convert",
+            "U4E00.pdf",
+            "--size",
+            "64",
+            "--padding",
+            "4",
+            "--centering",
+            "em-box",
+            "--em-bottom",
+            "-0.12",
+            "--background",
+            "#000000",
+        ])
+        .unwrap();
+        let Command::Convert(args) = cli.command else {
+            panic!("應爲 convert 子命令");
+        };
+
+        let options = args.render.options();
+        assert_eq!(options.size, 64);
+        assert_eq!(options.padding, 4);
+        assert_eq!(options.centering, Centering::EmBox);
+        assert_eq!(options.em_bottom, -0.12);
+        assert_eq!(options.background, Color(0, 0, 0));
+        // 未指定的項目取默認值
+        assert_eq!(options.foreground, RenderOptions::default().foreground);
+        assert_eq!(options.em_pixels(), 56.0);
+        assert_eq!(options.blank().dimensions(), (64, 64));
+
+        assert!(Cli::try_parse_from([
+            "cjk-unicode-pdf-to-xlsx",
+            "convert",
+            "a.pdf",
+            "--background",
+            "red"
+        ])
+        .is_err());
     }
 }
@@ -0,0 +1,133 @@
+use std::fmt::Write as _;
+
+use resvg::usvg::{self, tiny_skia_path::PathSegment, NodeKind, TreeParsing};
+
+use crate::error::{Error, Result};
+
+/// 路徑上的一點 (x, y)
+pub type Point = (f32, f32);
+
+/// 只含絕對坐標的路徑片段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// 以 em 爲單位、y 軸向上的字形輪廓
+///
+/// 圖表中的路徑可能含相對坐標、弧線等，解析後統一爲絕對坐標的直線與二次、三次曲線
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    pub segments: Vec<Segment>,
+}
+
+impl Outline {
+    /// 解析 `EntryGlyph.path` 等 em 坐標的路徑
+    pub fn parse(drawn: &str) -> Result<Self> {
+        // 交給 usvg 規範化，其間不作任何變換
+        let svg_data = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1"><path d="{}" fill="black"/></svg>"#,
+            drawn
+        );
+        let tree = usvg::Tree::from_data(svg_data.as_bytes(), &usvg::Options::default())
+            .map_err(|err| Error::Outline(err.to_string()))?;
+
+        let mut segments = vec![];
+        for node in tree.root.descendants() {
+            if let NodeKind::Path(ref path) = *node.borrow() {
+                let data = path
+                    .data
+                    .as_ref()
+                    .clone()
+                    .transform(path.transform)
+                    .ok_or_else(|| Error::Outline("路徑變換後無效".to_string()))?;
+                segments.extend(data.segments().map(|segment| match segment {
+                    PathSegment::MoveTo(p) => Segment::MoveTo((p.x, p.y)),
+                    PathSegment::LineTo(p) => Segment::LineTo((p.x, p.y)),
+                    PathSegment::QuadTo(p1, p) => Segment::QuadTo((p1.x, p1.y), (p.x, p.y)),
+                    PathSegment::CubicTo(p1, p2, p) => {
+                        Segment::CubicTo((p1.x, p1.y), (p2.x, p2.y), (p.x, p.y))
+                    }
+                    PathSegment::Close => Segment::Close,
+                }));
+            }
+        }
+
+        Ok(Self { segments })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// 路徑上所有點（含控制點）的範圍 (左, 下, 右, 上)
+    pub fn bounds(&self) -> Option<[f32; 4]> {
+        self.points().fold(None, |bounds, (x, y)| {
+            let [left, bottom, right, top] = bounds.unwrap_or([x, y, x, y]);
+            Some([left.min(x), bottom.min(y), right.max(x), top.max(y)])
+        })
+    }
+
+    fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.segments.iter().flat_map(|segment| match *segment {
+            Segment::MoveTo(p) | Segment::LineTo(p) => vec![p],
+            Segment::QuadTo(p1, p) => vec![p1, p],
+            Segment::CubicTo(p1, p2, p) => vec![p1, p2, p],
+            Segment::Close => vec![],
+        })
+    }
+
+    /// SVG 的路徑數據，y 軸翻轉爲向下，`top` 處爲 0
+    pub fn to_svg_path(&self, top: f32) -> String {
+        let point = |(x, y): Point| format!("{} {}", number(x), number(top - y));
+        let mut path = String::new();
+        for segment in &self.segments {
+            if !path.is_empty() {
+                path.push(' ');
+            }
+            match *segment {
+                Segment::MoveTo(p) => write!(path, "M{}", point(p)),
+                Segment::LineTo(p) => write!(path, "L{}", point(p)),
+                Segment::QuadTo(p1, p) => write!(path, "Q{} {}", point(p1), point(p)),
+                Segment::CubicTo(p1, p2, p) => {
+                    write!(path, "C{} {} {}", point(p1), point(p2), point(p))
+                }
+                Segment::Close => write!(path, "Z"),
+            }
+            .unwrap();
+        }
+
+        path
+    }
+}
+
+// 保留至萬分之一 em，去掉末尾的 0
+fn number(value: f32) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normalize_outline() {
+        let outline = Outline::parse("m.1 .2h.5v.3l-.5 0z").unwrap();
+        assert_eq!(outline.bounds(), Some([0.1, 0.2, 0.6, 0.5]));
+        assert_eq!(
+            outline.to_svg_path(1.0),
+            "M0.1 0.8 L0.6 0.8 L0.6 0.5 L0.1 0.5 Z"
+        );
+        assert_eq!(number(-0.00001), "0");
+        assert_eq!(number(1.0), "1");
+    }
+}
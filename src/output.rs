@@ -0,0 +1,921 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{self, File},
+    io::{BufWriter, Cursor, Write},
+    iter,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+use quick_xml::{
+    escape::{escape, partial_escape},
+    events::{BytesText, Event},
+    Reader, Writer,
+};
+use rust_xlsxwriter::DocProperties;
+use serde::Serialize;
+
+use crate::{
+    chart::{ChartEntry, ChartPage, EntryGlyph},
+    detect::ChartMetadata,
+    error::{Error, Result},
+    font::{build_font, glyph_name, FontGlyph},
+    image_gen::{render_drawn, RenderOptions, RenderedGlyph},
+    outline::Outline,
+    re,
+    report::Report,
+    source::{DictionaryRef, Region, SourceRef, StandardCode},
+    xlsx::Xlsx,
+    ConvertOptions,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Xlsx,
+    Json,
+    Csv,
+    /// 目錄，每個 (碼位, 字源) 一個 SVG 文件，附 `index.json`
+    Svg,
+    /// 目錄，每個地區一個 TrueType 字體
+    Ttf,
+}
+
+impl OutputFormat {
+    /// 依輸出文件的擴展名推斷格式
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let ext = path.as_ref().extension()?.to_str()?;
+        ext.parse().ok()
+    }
+
+    /// 未指定輸出時的默認路徑，如 `U4E00.xlsx`；SVG 與字體輸出爲目錄，如 `U4E00-svg`
+    pub fn default_path<P: AsRef<Path>>(&self, input: P) -> PathBuf {
+        let input = input.as_ref();
+        match self {
+            OutputFormat::Svg | OutputFormat::Ttf => {
+                let mut name = input.file_stem().unwrap_or_default().to_os_string();
+                name.push(format!("-{}", self));
+                input.with_file_name(name)
+            }
+            format => input.with_extension(format.to_string()),
+        }
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "svg" => Ok(OutputFormat::Svg),
+            "ttf" => Ok(OutputFormat::Ttf),
+            _ => Err(format!("不支持的輸出格式: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Svg => "svg",
+            OutputFormat::Ttf => "ttf",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 碼位在輸出中的次序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EntryOrder {
+    /// 圖表中的次序，即碼位的次序
+    #[default]
+    Codepoint,
+    /// 按字源所引用字典的卷、頁與字序，沒有字典字源的碼位排在最後
+    Dictionary,
+}
+
+impl EntryOrder {
+    /// 按此次序排列碼位，次序相同者保持原有次序
+    pub fn sort<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a ChartEntry>,
+    ) -> Vec<&'a ChartEntry> {
+        let mut entries = entries.into_iter().collect::<Vec<_>>();
+        if *self == EntryOrder::Dictionary {
+            entries.sort_by_cached_key(|entry| {
+                let dictionary_ref = entry.dictionary_ref();
+                (dictionary_ref.is_none(), dictionary_ref)
+            });
+        }
+
+        entries
+    }
+}
+
+impl FromStr for EntryOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "codepoint" => Ok(EntryOrder::Codepoint),
+            "dictionary" => Ok(EntryOrder::Dictionary),
+            _ => Err(format!("不支持的排序方式: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for EntryOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntryOrder::Codepoint => "codepoint",
+            EntryOrder::Dictionary => "dictionary",
+        };
+        f.write_str(name)
+    }
+}
+
+/// xlsx 中字形所在的列
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColumnLayout {
+    /// 按字源的次序依次排列
+    #[default]
+    Sequential,
+    /// 每個地區固定一列，首行爲表頭，缺少該地區字源的格子留空
+    Region,
+}
+
+impl FromStr for ColumnLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "sequential" => Ok(ColumnLayout::Sequential),
+            "region" => Ok(ColumnLayout::Region),
+            _ => Err(format!("不支持的列排列方式: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for ColumnLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnLayout::Sequential => "sequential",
+            ColumnLayout::Region => "region",
+        };
+        f.write_str(name)
+    }
+}
+
+/// 各地區所佔的列，依地區的次序排列；同一碼位有多個同地區字源時，該地區佔多列
+pub fn region_columns<'a>(entries: impl IntoIterator<Item = &'a ChartEntry>) -> Vec<Region> {
+    let mut counts = HashMap::new();
+    for entry in entries {
+        // 同地區的字源未必相鄰，須按地區合計
+        for (region, n) in entry.glyphs.iter().counts_by(|glyph| glyph.source.region) {
+            let count = counts.entry(region).or_insert(0);
+            *count = n.max(*count);
+        }
+    }
+
+    Region::ALL
+        .into_iter()
+        .flat_map(|region| iter::repeat_n(region, counts.get(&region).copied().unwrap_or(0)))
+        .collect()
+}
+
+pub fn write_output<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    options: &ConvertOptions,
+    report: &mut Report,
+) -> Result<()> {
+    match options.format {
+        OutputFormat::Xlsx => write_xlsx(pages, metadata, path, options, report),
+        OutputFormat::Json => write_json(pages, metadata, path, options.order),
+        OutputFormat::Csv => write_csv(pages, metadata, path, options.order),
+        OutputFormat::Svg => write_svg(pages, metadata, path, options, report),
+        OutputFormat::Ttf => write_fonts(pages, metadata, path, options, report),
+    }
+}
+
+/// 每 `options.limit` 頁寫入一個以碼位範圍命名、帶表頭的工作表，表內按 `options.order` 排列，
+/// 字形按 `options.columns` 分列、按 `options.render` 渲染，最後附上 `metadata` 工作表。
+/// 超出圖片範圍的字形記入 `report`；寬鬆模式下渲染失敗的字形以空白圖片代替並記入報告
+pub fn write_xlsx<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    options: &ConvertOptions,
+    report: &mut Report,
+) -> Result<()> {
+    let (limit, order, columns) = (options.limit, options.order, options.columns);
+    let mut xlsx = Xlsx::new();
+    let regions = match columns {
+        ColumnLayout::Sequential => vec![],
+        ColumnLayout::Region => region_columns(pages.iter().flat_map(|page| &page.entries)),
+    };
+    let mut label_counts = HashMap::new();
+
+    for (chunk_idx, chunk) in pages.chunks(limit.max(1) as usize).enumerate() {
+        if chunk_idx > 0 {
+            xlsx.next_sheet();
+        }
+
+        // 以本表的碼位範圍命名工作表，如 `4E00–5A9F`，重名時附上序號
+        let span = chunk
+            .iter()
+            .flat_map(|page| &page.entries)
+            .map(|entry| entry.codepoint)
+            .minmax()
+            .into_option();
+        if let Some((first, last)) = span {
+            let count = label_counts.entry((first, last)).or_insert(0);
+            *count += 1;
+            xlsx.set_sheet_name(&sheet_name(first, last, *count))?;
+        }
+
+        // 依次排列時取本表中字源最多的碼位的字源數
+        let col_max = match columns {
+            ColumnLayout::Sequential => chunk
+                .iter()
+                .flat_map(|page| &page.entries)
+                .map(|entry| entry.glyphs.len())
+                .max()
+                .unwrap_or_default(),
+            ColumnLayout::Region => regions.len(),
+        }
+        .max(1);
+        xlsx.set_glyph_columns(col_max)?;
+
+        let mut headers = vec!["碼位".to_string(), "字符".to_string()];
+        match columns {
+            ColumnLayout::Sequential => {
+                headers.extend((1..=col_max).map(|idx| format!("字源 {}", idx)))
+            }
+            ColumnLayout::Region => headers.extend(regions.iter().map(Region::to_string)),
+        }
+        xlsx.add_header(&headers)?;
+
+        for entry in order.sort(chunk.iter().flat_map(|page| &page.entries)) {
+            let mut cells = vec![];
+            for glyph in &entry.glyphs {
+                let image = match render_glyph(entry, glyph, &options.render) {
+                    Ok(rendered) => {
+                        if let Some(fallback) = rendered.fallback {
+                            report.warn(
+                                Some(entry.page),
+                                vec![entry.codepoint],
+                                format!(
+                                    "字形超出圖片範圍 ({} {}): {}",
+                                    entry.hex(),
+                                    glyph.source,
+                                    fallback
+                                ),
+                            );
+                        }
+                        rendered.image
+                    }
+                    Err(err) if options.lenient => {
+                        report.push(Some(entry.page), vec![entry.codepoint], &err);
+                        options.render.blank()
+                    }
+                    Err(err) => return Err(err),
+                };
+                // 字形下方註記標準中的區位或字典中的位置
+                let note = match glyph.source.standard_code() {
+                    Some(standard_code) => standard_code.to_string(),
+                    None => glyph
+                        .source
+                        .dictionary_ref()
+                        .map(|dictionary_ref| dictionary_ref.to_string())
+                        .unwrap_or_default(),
+                };
+                cells.push(Some((glyph.source.to_string(), image, note)));
+            }
+
+            if columns == ColumnLayout::Region {
+                // 依次填入該地區尚空的列
+                let mut placed = vec![None; regions.len()];
+                for (glyph, cell) in entry.glyphs.iter().zip(cells) {
+                    let col = (0..regions.len())
+                        .find(|&col| regions[col] == glyph.source.region && placed[col].is_none());
+                    match col {
+                        Some(col) => placed[col] = cell,
+                        None => {
+                            let err = Error::Invalid(format!(
+                                "{} ({}) 沒有可填入的地區列",
+                                entry.hex(),
+                                glyph.source
+                            ))
+                            .at_page(entry.page);
+                            if !options.lenient {
+                                return Err(err);
+                            }
+                            report.push(Some(entry.page), vec![entry.codepoint], &err);
+                        }
+                    }
+                }
+                cells = placed;
+            }
+
+            xlsx.add_row(&entry.hex(), entry.character, &cells, col_max)?;
+        }
+        xlsx.add_autofilter(col_max)?;
+    }
+
+    xlsx.set_properties(&doc_properties(metadata));
+    xlsx.add_info_sheet("metadata", &metadata.fields())?;
+
+    let zip_buf = xlsx.save_to_buffer()?;
+    rezip(&zip_buf, path)
+}
+
+// 如 `4E00–5A9F`、`4E00–5A9F (2)`
+fn sheet_name(first: u32, last: u32, count: usize) -> String {
+    match count {
+        1 => format!("{:04X}–{:04X}", first, last),
+        n => format!("{:04X}–{:04X} ({})", first, last, n),
+    }
+}
+
+// 渲染失敗時附上碼位與字源
+fn render_glyph(
+    entry: &ChartEntry,
+    glyph: &EntryGlyph,
+    render: &RenderOptions,
+) -> Result<RenderedGlyph> {
+    render_drawn(&glyph.path, render).map_err(|err| match err {
+        Error::Render(message) => {
+            Error::Render(format!("{} ({}): {}", entry.hex(), glyph.source, message))
+                .at_page(entry.page)
+        }
+        err => err,
+    })
+}
+
+fn doc_properties(metadata: &ChartMetadata) -> DocProperties {
+    let mut properties = DocProperties::new();
+    if let Some(block) = metadata.block {
+        properties = properties.set_title(block.name);
+    }
+    if let Some(version) = metadata.unicode_version {
+        properties = properties.set_subject(format!("Unicode {}", version));
+    }
+    for (name, value) in metadata.fields() {
+        properties = properties.set_custom_property(name, value.as_str());
+    }
+
+    properties
+}
+
+#[derive(Serialize)]
+struct JsonOutput<'a> {
+    metadata: &'a ChartMetadata,
+    entries: Vec<&'a ChartEntry>,
+}
+
+pub fn write_json<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    order: EntryOrder,
+) -> Result<()> {
+    let output = JsonOutput {
+        metadata,
+        entries: order.sort(pages.iter().flat_map(|page| &page.entries)),
+    };
+
+    let file = File::create(&path).map_err(|err| Error::file(path.as_ref(), err))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &output)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// 每個 (碼位, 字源) 一行，引用國家標準或字典的字源附上其中的位置，末尾幾列爲圖表的版本信息
+pub fn write_csv<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    order: EntryOrder,
+) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([
+        "codepoint",
+        "character",
+        "page",
+        "source",
+        "region",
+        "standard",
+        "plane",
+        "row",
+        "cell",
+        "standard_code",
+        "dictionary",
+        "volume",
+        "dictionary_page",
+        "position",
+        "block",
+        "unicode_version",
+        "chart_date",
+    ])?;
+
+    let block = metadata.block.map_or("", |block| block.name);
+    let unicode_version = metadata
+        .unicode_version
+        .map(|version| version.to_string())
+        .unwrap_or_default();
+    let chart_date = metadata.chart_date.clone().unwrap_or_default();
+
+    for entry in order.sort(pages.iter().flat_map(|page| &page.entries)) {
+        for glyph in &entry.glyphs {
+            let standard = glyph.source.standard_code();
+            let field =
+                |f: fn(&StandardCode) -> String| standard.as_ref().map(f).unwrap_or_default();
+            let dictionary_ref = glyph.source.dictionary_ref();
+            let dictionary_field = |f: fn(&DictionaryRef) -> String| {
+                dictionary_ref.as_ref().map(f).unwrap_or_default()
+            };
+            writer.write_record([
+                entry.hex(),
+                entry.character.to_string(),
+                entry.page.to_string(),
+                glyph.source.to_string(),
+                glyph.source.region.to_string(),
+                field(|code| code.standard.to_string()),
+                field(|code| {
+                    code.plane
+                        .map(|plane| plane.to_string())
+                        .unwrap_or_default()
+                }),
+                field(|code| code.row.to_string()),
+                field(|code| code.cell.to_string()),
+                field(|code| format!("{:04X}", code.code)),
+                dictionary_field(|dictionary_ref| dictionary_ref.dictionary.name().to_string()),
+                dictionary_field(|dictionary_ref| {
+                    dictionary_ref
+                        .volume
+                        .map(|volume| volume.to_string())
+                        .unwrap_or_default()
+                }),
+                dictionary_field(|dictionary_ref| dictionary_ref.page.to_string()),
+                dictionary_field(|dictionary_ref| dictionary_ref.position.to_string()),
+                block.to_string(),
+                unicode_version.clone(),
+                chart_date.clone(),
+            ])?;
+        }
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SvgIndex<'a> {
+    metadata: &'a ChartMetadata,
+    glyphs: Vec<SvgIndexEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct SvgIndexEntry<'a> {
+    codepoint: String,
+    character: char,
+    page: usize,
+    source: &'a SourceRef,
+    region: Region,
+    standard: Option<StandardCode>,
+    dictionary: Option<DictionaryRef>,
+    file: String,
+}
+
+/// 在目錄 `path` 中爲每個 (碼位, 字源) 寫入一個 SVG，如 `4E00-G0-523B.svg`，並附上列出各文件的 `index.json`
+///
+/// viewBox 即 em 框，按 `options.render.em_bottom` 定位；`options.svg_metadata` 時在 SVG 中附上碼位、字源與頁碼。
+/// 寬鬆模式下無法解析的字形記入報告並跳過
+pub fn write_svg<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    options: &ConvertOptions,
+    report: &mut Report,
+) -> Result<()> {
+    let dir = path.as_ref();
+    fs::create_dir_all(dir).map_err(|err| Error::file(dir, err))?;
+
+    let top = 1.0 + options.render.em_bottom as f32;
+    let unsafe_chars = re!(r"[^0-9A-Za-z.\-]");
+    let mut glyphs = vec![];
+    for entry in options
+        .order
+        .sort(pages.iter().flat_map(|page| &page.entries))
+    {
+        for glyph in &entry.glyphs {
+            let outline = match Outline::parse(&glyph.path) {
+                Ok(outline) => outline,
+                Err(err) if options.lenient => {
+                    report.push(Some(entry.page), vec![entry.codepoint], &err);
+                    continue;
+                }
+                Err(err) => return Err(err.at_page(entry.page)),
+            };
+
+            let file = unsafe_chars
+                .replace_all(&format!("{}-{}.svg", entry.hex(), glyph.source), "_")
+                .into_owned();
+            let file_path = dir.join(&file);
+            fs::write(
+                &file_path,
+                glyph_svg(entry, glyph, &outline, top, options.svg_metadata),
+            )
+            .map_err(|err| Error::file(&file_path, err))?;
+
+            glyphs.push(SvgIndexEntry {
+                codepoint: entry.hex(),
+                character: entry.character,
+                page: entry.page,
+                source: &glyph.source,
+                region: glyph.source.region,
+                standard: glyph.source.standard_code(),
+                dictionary: glyph.source.dictionary_ref(),
+                file,
+            });
+        }
+    }
+
+    let index_path = dir.join("index.json");
+    let file = File::create(&index_path).map_err(|err| Error::file(&index_path, err))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &SvgIndex { metadata, glyphs })?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn glyph_svg(
+    entry: &ChartEntry,
+    glyph: &EntryGlyph,
+    outline: &Outline,
+    top: f32,
+    with_metadata: bool,
+) -> String {
+    let source = escape(glyph.source.to_string());
+    let (attributes, title) = match with_metadata {
+        true => (
+            format!(
+                r#" data-codepoint="{}" data-source="{}" data-page="{}""#,
+                entry.hex(),
+                source,
+                entry.page
+            ),
+            format!(
+                "\n  <title>U+{} {} {}</title>",
+                entry.hex(),
+                escape(entry.character.to_string()),
+                source
+            ),
+        ),
+        false => Default::default(),
+    };
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 1 1" width="1em" height="1em"{}>{}
+  <path d="{}"/>
+</svg>
+"#,
+        attributes,
+        title,
+        outline.to_svg_path(top)
+    )
+}
+
+/// 在目錄 `path` 中爲每個地區寫入一個字體，如 `T.ttf`，字族名爲區塊名加地區代碼，如 `CJK Unified Ideographs T`
+///
+/// 字形名取自字源，碼位映射到該地區的第一個字形；em 框按 `options.render.em_bottom` 定位。
+/// 寬鬆模式下無法解析的字形記入報告並跳過
+pub fn write_fonts<P: AsRef<Path>>(
+    pages: &[ChartPage],
+    metadata: &ChartMetadata,
+    path: P,
+    options: &ConvertOptions,
+    report: &mut Report,
+) -> Result<()> {
+    let dir = path.as_ref();
+    fs::create_dir_all(dir).map_err(|err| Error::file(dir, err))?;
+
+    let mut regions: HashMap<Region, Vec<FontGlyph>> = HashMap::new();
+    for entry in pages.iter().flat_map(|page| &page.entries) {
+        for glyph in &entry.glyphs {
+            let outline = match Outline::parse(&glyph.path) {
+                Ok(outline) => outline,
+                Err(err) if options.lenient => {
+                    report.push(Some(entry.page), vec![entry.codepoint], &err);
+                    continue;
+                }
+                Err(err) => return Err(err.at_page(entry.page)),
+            };
+            regions
+                .entry(glyph.source.region)
+                .or_default()
+                .push(FontGlyph {
+                    name: glyph_name(&glyph.source.to_string()),
+                    codepoint: Some(entry.codepoint),
+                    outline,
+                });
+        }
+    }
+
+    let block = metadata.block.map_or("CJK Chart", |block| block.name);
+    for (region, glyphs) in regions.into_iter().sorted_by_key(|(region, _)| *region) {
+        let family = format!("{} {}", block, region);
+        let font = build_font(&family, &glyphs, options.render.em_bottom as f32)?;
+        let font_path = dir.join(format!("{}.ttf", region));
+        fs::write(&font_path, font).map_err(|err| Error::file(&font_path, err))?;
+    }
+
+    Ok(())
+}
+
+// rust_xlsxwriter 只在工作表名含空格、`!` 或引號時加引號，而以數字開頭或含 `–` 的名稱
+// 如 `4E00–5A9F` 在自動篩選的定義名稱中也須加引號，否則 Excel 會提示修復
+fn quote_defined_names(workbook_xml: &[u8]) -> Result<Vec<u8>> {
+    let xml_err =
+        |err: &dyn fmt::Display| Error::Invalid(format!("無法處理 workbook.xml: {}", err));
+    let mut reader = Reader::from_reader(workbook_xml);
+    let mut writer = Writer::new(Vec::with_capacity(workbook_xml.len()));
+    let mut buf = vec![];
+    let mut in_defined_name = false;
+
+    loop {
+        let event = match reader
+            .read_event_into(&mut buf)
+            .map_err(|err| xml_err(&err))?
+        {
+            Event::Eof => break,
+            Event::Start(element) => {
+                in_defined_name = element.name().as_ref() == b"definedName";
+                Event::Start(element)
+            }
+            Event::End(element) => {
+                in_defined_name = false;
+                Event::End(element)
+            }
+            Event::Text(text) if in_defined_name => {
+                let formula = text.unescape().map_err(|err| xml_err(&err))?;
+                let quoted = quote_reference(&formula);
+                Event::Text(BytesText::from_escaped(
+                    partial_escape(&quoted).into_owned(),
+                ))
+            }
+            event => event,
+        };
+        writer.write_event(event)?;
+        buf.clear();
+    }
+
+    Ok(writer.into_inner())
+}
+
+// `Sheet!$A$1:$C$4` 改爲 `'Sheet'!$A$1:$C$4`，名稱中的引號寫作兩個
+fn quote_reference(formula: &str) -> String {
+    match formula.rsplit_once('!') {
+        Some((sheet, range)) if !sheet.starts_with('\'') => {
+            format!("'{}'!{}", sheet.replace('\'', "''"), range)
+        }
+        _ => formula.to_string(),
+    }
+}
+
+// 以 deflate 重新壓縮 rust_xlsxwriter 的輸出
+fn rezip<P: AsRef<Path>>(zip_buf: &[u8], path: P) -> Result<()> {
+    let mut zip_archive = zip::ZipArchive::new(Cursor::new(zip_buf))?;
+    let file = File::create(&path).map_err(|err| Error::file(path.as_ref(), err))?;
+    let mut zip_writer = zip::ZipWriter::new(file);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(Some(6));
+
+    let mut buf_writer = vec![];
+
+    for i in 0..zip_archive.len() {
+        let mut file = zip_archive.by_index(i)?;
+        let filepath = match file.enclosed_name() {
+            Some(path) => path.to_string_lossy().replace('\\', "/"),
+            None => continue,
+        };
+
+        std::io::copy(&mut file, &mut buf_writer)?;
+        if filepath == "xl/workbook.xml" {
+            buf_writer = quote_defined_names(&buf_writer)?;
+        }
+
+        zip_writer.start_file(filepath, options)?;
+        zip_writer.write_all(&buf_writer)?;
+
+        buf_writer.clear();
+    }
+
+    zip_writer.finish()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // 每個字源畫一個方框
+    fn entry(sources: &[&str]) -> ChartEntry {
+        ChartEntry {
+            codepoint: 0x4E00,
+            character: '一',
+            page: 1,
+            glyphs: sources
+                .iter()
+                .map(|source| EntryGlyph {
+                    source: source.parse().unwrap(),
+                    path: "M.1 .1H.9V.9H.1Z".to_string(),
+                })
+                .collect(),
+        }
+    }
+
+    fn read_part(path: &Path, name: &str) -> String {
+        let mut zip_archive = zip::ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let mut text = String::new();
+        std::io::Read::read_to_string(&mut zip_archive.by_name(name).unwrap(), &mut text).unwrap();
+        text
+    }
+
+    #[test]
+    fn xlsx_sheets() {
+        // (用例, 每頁各碼位的字源, 每表頁數, 分列方式, 各部件中應有的片段)
+        type Case<'a> = (
+            &'a str,
+            Vec<Vec<&'a [&'a str]>>,
+            u32,
+            ColumnLayout,
+            Vec<(&'a str, Vec<&'a str>)>,
+        );
+        let cases: [Case; 3] = [
+            // 首表最寬的一行有三個字源，次表只有一個
+            (
+                "columns",
+                vec![
+                    vec![&["G0-523B"], &["G0-523B", "T1-4421", "J0-3021"]],
+                    vec![&["K0-4A21"]],
+                ],
+                1,
+                ColumnLayout::Sequential,
+                vec![
+                    (
+                        "xl/worksheets/sheet1.xml",
+                        vec![r#"<autoFilter ref="A1:E7"/>"#, r#"<col min="3" max="5" "#],
+                    ),
+                    (
+                        "xl/worksheets/sheet2.xml",
+                        vec![r#"<autoFilter ref="A1:C4"/>"#, r#"<col min="3" max="3" "#],
+                    ),
+                ],
+            ),
+            // 重名的工作表附上序號，定義名稱中的工作表名加引號；字形下方註記標準中的區位
+            (
+                "names",
+                vec![vec![&["G0-523B"]], vec![&["G0-523B"]]],
+                1,
+                ColumnLayout::Sequential,
+                vec![
+                    (
+                        "xl/workbook.xml",
+                        vec![
+                            r#"<sheet name="4E00–4E00" "#,
+                            r#"<sheet name="4E00–4E00 (2)" "#,
+                            r#"<sheet name="metadata" "#,
+                            ">'4E00–4E00'!$A$1:$C$4</definedName>",
+                            ">'4E00–4E00 (2)'!$A$1:$C$4</definedName>",
+                        ],
+                    ),
+                    ("xl/sharedStrings.xml", vec![">GB 2312 50-27<"]),
+                ],
+            ),
+            // 每個地區一列，表頭爲地區代碼
+            (
+                "regions",
+                vec![vec![&["T1-4421", "G0-523B"], &["UTC-00001"]]],
+                100,
+                ColumnLayout::Region,
+                vec![
+                    (
+                        "xl/worksheets/sheet1.xml",
+                        vec![r#"<autoFilter ref="A1:E7"/>"#],
+                    ),
+                    ("xl/sharedStrings.xml", vec![">G<", ">T<", ">UTC<"]),
+                ],
+            ),
+        ];
+
+        let dir = crate::test_dir("xlsx");
+        for (name, sources, limit, columns, parts) in cases {
+            let pages = sources
+                .iter()
+                .enumerate()
+                .map(|(idx, entries)| {
+                    let mut page = ChartPage::without_glyphs(idx + 1, vec![]);
+                    page.entries = entries.iter().map(|sources| entry(sources)).collect();
+                    page
+                })
+                .collect_vec();
+            let path = dir.join(format!("{}.xlsx", name));
+            let mut options = ConvertOptions::new(None);
+            options.limit = limit;
+            options.columns = columns;
+            write_xlsx(
+                &pages,
+                &ChartMetadata::default(),
+                &path,
+                &options,
+                &mut Report::default(),
+            )
+            .unwrap();
+
+            for (part, needles) in parts {
+                let text = read_part(&path, part);
+                for needle in needles {
+                    assert!(
+                        text.contains(needle),
+                        "{}: {} 中缺少 {}",
+                        name,
+                        part,
+                        needle
+                    );
+                }
+            }
+        }
+
+        assert_eq!(sheet_name(0x20000, 0x2A6DF, 1), "20000–2A6DF");
+        assert_eq!(quote_reference("'a''b'!$A$1"), "'a''b'!$A$1");
+        assert_eq!(quote_reference("it's!$A$1"), "'it''s'!$A$1");
+    }
+
+    #[test]
+    fn svg_index() {
+        let mut page = ChartPage::without_glyphs(1, vec![]);
+        page.entries.push(entry(&["G0-523B", "GHZ-74603.02"]));
+        let dir = crate::test_dir("svg");
+        let mut options = ConvertOptions::new(None);
+        options.format = OutputFormat::Svg;
+        write_svg(
+            &[page],
+            &ChartMetadata::default(),
+            &dir,
+            &options,
+            &mut Report::default(),
+        )
+        .unwrap();
+
+        let index: serde_json::Value =
+            serde_json::from_slice(&fs::read(dir.join("index.json")).unwrap()).unwrap();
+        let glyphs = &index["glyphs"];
+        assert_eq!(glyphs[0]["file"], "4E00-G0-523B.svg");
+        assert_eq!(glyphs[0]["standard"]["standard"], "GB 2312");
+        assert_eq!(glyphs[0]["standard"]["code"], "523B");
+        assert!(glyphs[0]["dictionary"].is_null());
+        assert_eq!(glyphs[1]["dictionary"]["dictionary"], "漢語大字典");
+        assert_eq!(glyphs[1]["dictionary"]["volume"], 7);
+        assert_eq!(glyphs[1]["dictionary"]["position"], 2);
+        assert!(dir.join("4E00-GHZ-74603.02.svg").exists());
+    }
+
+    #[test]
+    fn count_region_columns() {
+        let entries = [
+            entry(&["G0-523B", "T1-4421", "GHZ-10001.01", "J0-3021"]),
+            entry(&["T1-4421", "T3-2144"]),
+            // UTC 與其他 U 字源各佔一列
+            entry(&["UTC-00001", "UCI-00001", "SAT-04081"]),
+        ];
+        assert_eq!(
+            region_columns(&entries),
+            [
+                Region::G,
+                Region::G,
+                Region::T,
+                Region::T,
+                Region::J,
+                Region::S,
+                Region::U,
+                Region::UTC
+            ]
+        );
+    }
+}
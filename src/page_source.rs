@@ -0,0 +1,125 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    error::{Error, Result},
+    pdf::PdfDocument,
+    re, Backend,
+};
+
+/// PDF 文件信息字典中的元數據
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocumentMetadata {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub creator: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+    pub modification_date: Option<String>,
+}
+
+/// 頁面 SVG 的來源，頁碼從 0 開始
+pub trait PageSource {
+    fn page_count(&self) -> usize;
+
+    fn page_svg(&self, page_idx: usize) -> Result<String>;
+
+    fn metadata(&self) -> DocumentMetadata {
+        DocumentMetadata::default()
+    }
+}
+
+/// 依後端打開 PDF；若 `input` 爲目錄，則讀取其中預先導出的 SVG
+pub fn open_page_source(input: &str, backend: Backend) -> Result<Box<dyn PageSource>> {
+    if Path::new(input).is_dir() {
+        return Ok(Box::new(DirPageSource::open(input)?));
+    }
+
+    match backend {
+        Backend::Native => Ok(Box::new(PdfDocument::open(input)?)),
+        #[cfg(feature = "pymupdf")]
+        Backend::PyMuPdf => Ok(Box::new(crate::pymupdf::PyMuPdfDocument::open(input)?)),
+    }
+}
+
+/// 導出頁面時使用的文件名，如 `page-001.svg`
+pub fn page_file_name(page_idx: usize) -> String {
+    format!("page-{:03}.svg", page_idx)
+}
+
+/// 由 `page-NNN.svg` 文件組成的目錄
+pub struct DirPageSource {
+    dir: PathBuf,
+    page_count: usize,
+}
+
+impl DirPageSource {
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let name_regex = re!(r"^page-(\d+)\.svg$");
+
+        let mut page_count = 0;
+        let entries = fs::read_dir(&dir).map_err(|err| Error::file(dir.as_ref(), err))?;
+        for entry in entries {
+            let file_name = entry?.file_name();
+            let Some(c) = name_regex.captures(file_name.to_str().unwrap_or_default()) else {
+                continue;
+            };
+            let Ok(page_idx) = c[1].parse::<usize>() else {
+                continue;
+            };
+            page_count = page_count.max(page_idx + 1);
+        }
+
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            page_count,
+        })
+    }
+}
+
+impl PageSource for DirPageSource {
+    fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    fn page_svg(&self, page_idx: usize) -> Result<String> {
+        let path = self.dir.join(page_file_name(page_idx));
+        fs::read_to_string(&path).map_err(|err| Error::file(path, err))
+    }
+}
+
+/// 保存在內存中的頁面，主要用於測試
+#[derive(Debug, Clone, Default)]
+pub struct MemoryPageSource {
+    pub pages: Vec<String>,
+    pub metadata: DocumentMetadata,
+}
+
+impl MemoryPageSource {
+    pub fn new<S: Into<String>>(pages: impl IntoIterator<Item = S>) -> Self {
+        Self {
+            pages: pages.into_iter().map(Into::into).collect(),
+            metadata: DocumentMetadata::default(),
+        }
+    }
+}
+
+impl PageSource for MemoryPageSource {
+    fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    fn page_svg(&self, page_idx: usize) -> Result<String> {
+        self.pages
+            .get(page_idx)
+            .cloned()
+            .ok_or_else(|| Error::Invalid(format!("頁碼 {} 超出範圍", page_idx)))
+    }
+
+    fn metadata(&self) -> DocumentMetadata {
+        self.metadata.clone()
+    }
+}
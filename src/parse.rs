@@ -1,57 +1,242 @@
-use std::{collections::HashMap, ops::RangeBounds};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ops::{RangeBounds, RangeInclusive},
+};
 
-use duplicate::duplicate_item;
 use itertools::Itertools;
+use quick_xml::{
+    events::{attributes::Attribute, BytesStart, Event},
+    Reader,
+};
+
+use crate::{
+    blocks::block_of,
+    error::{Error, Result},
+    layout::{font_size, LayoutProfile},
+    re,
+};
+
+/// 二維仿射變換 `matrix(a,b,c,d,e,f)`
+type Matrix = [f64; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// 先施加 `inner` 再施加 `outer`
+fn compose(outer: &Matrix, inner: &Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = outer;
+    [
+        a * inner[0] + c * inner[1],
+        b * inner[0] + d * inner[1],
+        a * inner[2] + c * inner[3],
+        b * inner[2] + d * inner[3],
+        a * inner[4] + c * inner[5] + e,
+        b * inner[4] + d * inner[5] + f,
+    ]
+}
 
-use crate::{re, utils::EnhanceVec};
+/// 解析 `transform` 屬性，支持 `matrix`、`translate`、`scale` 及其組合
+fn parse_transform(transform: &str) -> Result<Matrix> {
+    let err = || Error::MalformedMatrix(transform.to_string());
+    let mut matrix = IDENTITY;
+    let mut rest = transform.trim();
+
+    while !rest.is_empty() {
+        let (name, tail) = rest.split_once('(').ok_or_else(err)?;
+        let (args, tail) = tail.split_once(')').ok_or_else(err)?;
+        let args = args
+            .split(|ch: char| ch == ',' || ch.is_whitespace())
+            .filter(|arg| !arg.is_empty())
+            .map(str::parse::<f64>)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+
+        let step = match (name.trim(), args.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => [a, b, c, d, e, f],
+            ("translate", &[x]) => [1.0, 0.0, 0.0, 1.0, x, 0.0],
+            ("translate", &[x, y]) => [1.0, 0.0, 0.0, 1.0, x, y],
+            ("scale", &[k]) => [k, 0.0, 0.0, k, 0.0, 0.0],
+            ("scale", &[x, y]) => [x, 0.0, 0.0, y, 0.0, 0.0],
+            _ => return Err(err()),
+        };
+        matrix = compose(&matrix, &step);
+        rest = tail.trim_start_matches(|ch: char| ch == ',' || ch.is_whitespace());
+    }
 
-pub fn parse_font_drawn(data: &str) -> HashMap<&str, &str> {
-    let defs_regex = re!(r"<defs>.*?</defs>");
-    let defs_tag = defs_regex.find(&data).unwrap().as_str();
+    Ok(matrix)
+}
 
-    let path_regex = re!(r#"<path.*?id="(.*?)".*?d="(.*?)".*?/>"#);
+/// 讀出元素的全部屬性
+fn attributes_of<'e>(element: &'e BytesStart<'_>) -> Result<Vec<Attribute<'e>>> {
+    element
+        .attributes()
+        .map(|attr| attr.map_err(|err| Error::Svg(err.to_string())))
+        .collect()
+}
+
+/// 依本地名稱取反轉義後的屬性值，`xlink:href` 與 `href` 皆可匹配 `href`
+fn attr_value<'e>(attrs: &[Attribute<'e>], name: &str) -> Result<Option<Cow<'e, str>>> {
+    attrs
+        .iter()
+        .find(|attr| attr.key.local_name().as_ref() == name.as_bytes())
+        .map(|attr| attr.unescape_value().map_err(Error::from))
+        .transpose()
+}
+
+// 其中的內容只作定義，不會直接繪製
+const NON_RENDERED: &[&[u8]] = &[b"defs", b"symbol", b"clipPath", b"mask", b"pattern"];
+
+/// 讀取 `<defs>` 中的字形輪廓，`<symbol>` 取其中第一個 `<path>`
+pub fn parse_font_drawn(data: &str) -> Result<HashMap<String, String>> {
+    let mut reader = Reader::from_str(data);
     let mut font_dic = HashMap::new();
-    path_regex.captures_iter(defs_tag).for_each(|c| {
-        let font_name = c.get(1).unwrap().as_str();
-        let d = c.get(2).unwrap().as_str();
-        font_dic.insert(font_name, d);
-    });
+    let mut found_defs = false;
+    let mut defs_depth = 0;
+    let mut symbol = None;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => match element.local_name().as_ref() {
+                b"defs" => {
+                    found_defs = true;
+                    defs_depth += 1;
+                }
+                b"symbol" if defs_depth > 0 => {
+                    symbol = attr_value(&attributes_of(&element)?, "id")?.map(Cow::into_owned);
+                }
+                b"path" if defs_depth > 0 => {
+                    insert_path(&mut font_dic, &mut symbol, &attributes_of(&element)?)?
+                }
+                _ => {}
+            },
+            Event::Empty(element) => match element.local_name().as_ref() {
+                b"defs" => found_defs = true,
+                b"path" if defs_depth > 0 => {
+                    insert_path(&mut font_dic, &mut symbol, &attributes_of(&element)?)?
+                }
+                _ => {}
+            },
+            Event::End(element) => match element.local_name().as_ref() {
+                b"defs" => defs_depth -= 1,
+                b"symbol" => symbol = None,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if !found_defs {
+        return Err(Error::MissingElement("defs"));
+    }
 
-    font_dic
+    Ok(font_dic)
 }
 
-pub fn parse_details(data: &str) -> Vec<(String, &str, Vec<f64>)> {
-    let g_regex = re!(r"<g.*?>(.*?)</g>");
-    let g_tag = g_regex.find(&data).unwrap().as_str();
-    let use_regex = re!(
-        r##"<use.*?data-text="(.*?)".*?xlink:href="#(.*?)".*?transform="matrix\((.*?)\)".*?>"##
-    );
-    let use_list: Vec<_> = use_regex
-        .captures_iter(g_tag)
-        .map(|c| {
-            let ch = c.get(1).unwrap().as_str().trim();
-            let ch = if ch.starts_with("&#x") {
-                let ch_part = &ch[3..ch.len() - 1];
-                let codepoint = u32::from_str_radix(ch_part, 16).unwrap();
-                char::from_u32(codepoint).unwrap().to_string().to_string()
-            } else {
-                ch.to_string()
-            };
-
-            let font_name = c.get(2).unwrap().as_str();
-
-            let matrix: Vec<_> = c
-                .get(3)
-                .unwrap()
-                .as_str()
-                .split(",")
-                .map(|each| each.parse::<f64>().unwrap())
-                .collect();
-            (ch, font_name, matrix)
-        })
-        .collect();
+fn insert_path(
+    font_dic: &mut HashMap<String, String>,
+    symbol: &mut Option<String>,
+    attrs: &[Attribute<'_>],
+) -> Result<()> {
+    let Some(d) = attr_value(attrs, "d")? else {
+        return Ok(());
+    };
+    if let Some(id) = symbol.take() {
+        font_dic.insert(id, d.into_owned());
+    } else if let Some(id) = attr_value(attrs, "id")? {
+        font_dic.insert(id.into_owned(), d.into_owned());
+    }
 
-    use_list
+    Ok(())
+}
+
+/// 讀取所有繪製出的 `<use>`：文字、引用的字形及疊加了外層 `<g>` 後的變換矩陣
+pub fn parse_details(data: &str) -> Result<Vec<(String, String, Vec<f64>)>> {
+    let mut reader = Reader::from_str(data);
+    let mut res = vec![];
+    let mut found_g = false;
+    let mut hidden_depth = 0;
+    // 外層各個 <g> 疊加後的變換
+    let mut group_stack = vec![IDENTITY];
+
+    loop {
+        let (element, is_empty) = match reader.read_event()? {
+            Event::Start(element) => (element, false),
+            Event::Empty(element) => (element, true),
+            Event::End(element) => {
+                let name = element.local_name();
+                if NON_RENDERED.contains(&name.as_ref()) {
+                    hidden_depth -= 1;
+                } else if name.as_ref() == b"g" {
+                    group_stack.pop();
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+
+        let name = element.local_name();
+        if NON_RENDERED.contains(&name.as_ref()) {
+            if !is_empty {
+                hidden_depth += 1;
+            }
+        } else if name.as_ref() == b"g" {
+            found_g = true;
+            if !is_empty {
+                let attrs = attributes_of(&element)?;
+                let outer = group_stack.last().unwrap_or(&IDENTITY);
+                let matrix = match attr_value(&attrs, "transform")? {
+                    Some(transform) => compose(outer, &parse_transform(&transform)?),
+                    None => *outer,
+                };
+                group_stack.push(matrix);
+            }
+        } else if name.as_ref() == b"use" && hidden_depth == 0 {
+            let attrs = attributes_of(&element)?;
+            if let Some(detail) = parse_use(&attrs, group_stack.last().unwrap_or(&IDENTITY))? {
+                res.push(detail);
+            }
+        }
+    }
+
+    if !found_g {
+        return Err(Error::MissingElement("g"));
+    }
+
+    Ok(res)
+}
+
+fn parse_use(
+    attrs: &[Attribute<'_>],
+    outer: &Matrix,
+) -> Result<Option<(String, String, Vec<f64>)>> {
+    let Some(text) = attrs.iter().find(|attr| attr.key.as_ref() == b"data-text") else {
+        return Ok(None);
+    };
+    let ch = text
+        .unescape_value()
+        .map_err(|_| Error::InvalidCodepoint(String::from_utf8_lossy(&text.value).into_owned()))?;
+
+    let Some(href) = attr_value(attrs, "href")? else {
+        return Ok(None);
+    };
+    let font_name = href.strip_prefix('#').unwrap_or(&href).to_string();
+
+    let mut matrix = match attr_value(attrs, "transform")? {
+        Some(transform) => compose(outer, &parse_transform(&transform)?),
+        None => *outer,
+    };
+    let coordinate = |name| -> Result<Option<f64>> {
+        Ok(attr_value(attrs, name)?.and_then(|value| value.parse().ok()))
+    };
+    let (x, y) = (coordinate("x")?, coordinate("y")?);
+    if x.is_some() || y.is_some() {
+        let translate = [1.0, 0.0, 0.0, 1.0, x.unwrap_or(0.0), y.unwrap_or(0.0)];
+        matrix = compose(&matrix, &translate);
+    }
+
+    Ok(Some((ch.trim().to_string(), font_name, matrix.to_vec())))
 }
 
 #[derive(Debug)]
@@ -63,13 +248,16 @@ pub struct ChartSource {
 }
 
 impl ChartSource {
-    pub fn new(detail_list: &Vec<(String, &str, Vec<f64>)>, threshold: f64) -> Vec<ChartSource> {
+    pub fn new(
+        detail_list: &[(String, String, Vec<f64>)],
+        layout: &LayoutProfile,
+    ) -> Vec<ChartSource> {
         let mut res = vec![];
         let start_letter_regex = re!(r"^[a-zA-Z]");
 
         for (k, g) in &detail_list
             .iter()
-            .filter(|&(_, _, mat)| mat[0] == 6.0)
+            .filter(|&(_, _, mat)| layout.is_source(font_size(mat)))
             .group_by(|&(_, _, mat)| mat[5])
         {
             let mut p = g.peekable();
@@ -92,7 +280,7 @@ impl ChartSource {
                         None => mat_next,
                     };
 
-                    if (mat_peek[4] - mat_next[4]).abs() > threshold {
+                    if (mat_peek[4] - mat_next[4]).abs() > layout.source_gap {
                         if start_letter_regex.is_match(&ch_res) {
                             let cs = ChartSource {
                                 source: ch_res.clone(),
@@ -137,13 +325,23 @@ pub struct ChartCode {
     pub y: f64,
 }
 
+fn parse_code(hex: &str) -> Result<(u32, char)> {
+    u32::from_str_radix(hex, 16)
+        .ok()
+        .and_then(|codepoint| Some((codepoint, char::from_u32(codepoint)?)))
+        .ok_or_else(|| Error::InvalidCodepoint(hex.to_string()))
+}
+
 impl ChartCode {
-    pub fn new(detail_list: &Vec<(String, &str, Vec<f64>)>, threshold: f64) -> Vec<ChartCode> {
+    pub fn new(
+        detail_list: &[(String, String, Vec<f64>)],
+        layout: &LayoutProfile,
+    ) -> Result<Vec<ChartCode>> {
         let mut res = vec![];
 
         for (k, g) in &detail_list
             .iter()
-            .filter(|&(_, _, mat)| mat[0] == 9.9998)
+            .filter(|&(_, _, mat)| layout.is_code(font_size(mat)))
             .group_by(|&(_, _, mat)| mat[5])
         {
             let mut p = g.peekable();
@@ -166,10 +364,9 @@ impl ChartCode {
                         None => mat_next,
                     };
 
-                    if (mat_peek[4] - mat_next[4]).abs() > threshold {
+                    if (mat_peek[4] - mat_next[4]).abs() > layout.code_gap {
                         if ch_res.len() >= 4 {
-                            let codepoint = u32::from_str_radix(&ch_res, 16).unwrap();
-                            let hanzi = char::from_u32(codepoint).unwrap();
+                            let (codepoint, hanzi) = parse_code(&ch_res)?;
                             let cs = ChartCode {
                                 codepoint,
                                 hanzi,
@@ -186,8 +383,7 @@ impl ChartCode {
                     }
                 } else {
                     if ch_res.len() >= 4 {
-                        let codepoint = u32::from_str_radix(&ch_res, 16).unwrap();
-                        let hanzi = char::from_u32(codepoint).unwrap();
+                        let (codepoint, hanzi) = parse_code(&ch_res)?;
                         let cs = ChartCode {
                             codepoint,
                             hanzi,
@@ -204,7 +400,7 @@ impl ChartCode {
             }
         }
 
-        res
+        Ok(res)
     }
 
     pub fn hex(&self) -> String {
@@ -212,125 +408,293 @@ impl ChartCode {
     }
 }
 
+// 圖表中除中日韓區塊外也可能作爲字形出現的範圍：注音、私用區、第四平面及 U+4DCC
+const CHART_EXTRA_RANGES: &[RangeInclusive<u32>] = &[
+    0x3100..=0x312F,
+    0x31A0..=0x31BF,
+    0x4DCC..=0x4DCC,
+    0xE000..=0xF8FF,
+    0x40000..=0x4FFFF,
+    0xF0000..=0xFFFFD,
+    0x100000..=0x10FFFD,
+];
+
+pub(crate) fn is_chart_glyph<R: RangeBounds<u32>>(ch: &str, codepoint_range: &R) -> bool {
+    let Some(codepoint) = ch.chars().next().map(u32::from) else {
+        return false;
+    };
+
+    codepoint_range.contains(&codepoint)
+        || block_of(codepoint).is_some()
+        || CHART_EXTRA_RANGES
+            .iter()
+            .any(|range| range.contains(&codepoint))
+}
+
 #[derive(Debug)]
 pub struct ChartGraph<'a> {
     pub ch: char,
     pub drawn: &'a str,
     pub x: f64,
     pub y: f64,
+    pub size: f64,
+}
+
+impl ChartGraph<'_> {
+    /// 字形 em 框的橫向中點
+    pub fn center_x(&self) -> f64 {
+        self.x + self.size / 2.0
+    }
 }
 
 impl<'b> ChartGraph<'b> {
     pub fn new<'a, R: RangeBounds<u32>>(
-        detail_list: &Vec<(String, &str, Vec<f64>)>,
-        font_dic: &'a HashMap<&str, &str>,
+        detail_list: &[(String, String, Vec<f64>)],
+        font_dic: &'a HashMap<String, String>,
         codepoint_range: &R,
-    ) -> Vec<ChartGraph<'a>> {
-        let codepoint_begin = match codepoint_range.start_bound() {
-            std::ops::Bound::Included(&num) => format!("\\u{{{:X}}}", num),
-            std::ops::Bound::Excluded(&num) => format!("\\u{{{:X}}}", num + 1),
-            std::ops::Bound::Unbounded => r"\u{0000}".to_string(),
-        };
-        let codepoint_end = match codepoint_range.end_bound() {
-            std::ops::Bound::Included(&num) => format!("\\u{{{:X}}}", num),
-            std::ops::Bound::Excluded(&num) => format!("\\u{{{:X}}}", num - 1),
-            std::ops::Bound::Unbounded => r"\u{10FFFF}".to_string(),
-        };
-        let additional_range_str = format!(r"{}-{}", codepoint_begin, codepoint_end);
-        let condition_str = r"[\u{3100}-\u{312F}\u{31A0}-\u{31BF}\u{4E00}-\u{9FFF}\u{3400}-\u{4DBF}\u{20000}-\u{2A6DF}\u{2A700}-\u{2B73F}\u{2B740}-\u{2B81F}\u{2B820}-\u{2CEAF}\u{2CEB0}-\u{2EBEF}\u{30000}-\u{3134F}\u{31350}-\u{323AF}\u{F900}-\u{FAFF}\u{2F800}-\u{2FA1F}\u{2F00}-\u{2FDF}\u{2E80}-\u{2EFF}\u{31C0}-\u{31EF}\u{2FF0}-\u{2FFF}\u{E000}-\u{F8FF}\u{F0000}-\u{FFFFD}\u{100000}-\u{10FFFD}䷌\u{40000}-\u{4FFFF}".to_string() + &additional_range_str + "]";
-        static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
-        let character_regex = RE.get_or_init(|| {
-            regex::RegexBuilder::new(&condition_str)
-                .dot_matches_new_line(true)
-                .build()
-                .unwrap()
-        });
-
-        let res: Vec<_> = detail_list
+        layout: &LayoutProfile,
+    ) -> Result<Vec<ChartGraph<'a>>> {
+        detail_list
             .iter()
-            .filter(|&(ch, _, mat)| character_regex.is_match(ch) && mat[0] > 6.0)
-            .map(|(ch, font_name, matrix)| ChartGraph {
-                ch: ch.chars().next().unwrap(),
-                drawn: font_dic[font_name],
-                x: matrix[4],
-                y: matrix[5],
+            .filter(|&(ch, _, mat)| {
+                is_chart_glyph(ch, codepoint_range) && layout.is_glyph(font_size(mat))
             })
-            .collect();
-
-        res
+            .map(|(ch, font_name, matrix)| {
+                Ok(ChartGraph {
+                    ch: ch.chars().next().unwrap_or_default(),
+                    drawn: font_dic
+                        .get(font_name)
+                        .ok_or_else(|| Error::UnknownFont(font_name.to_string()))?
+                        .as_str(),
+                    x: matrix[4],
+                    y: matrix[5],
+                    size: font_size(matrix),
+                })
+            })
+            .collect()
     }
 }
 
-#[duplicate_item(
-    [
-        name [get_row_range_from_code_vec]
-        method_1 [sort_x_y]
-        method_2 [x_min]
-    ]
-    [
-        name [get_col_range_from_code_vec]
-        method_1 [sort_y_x]
-        method_2 [y]
-    ]
-)]
-pub fn name(code_vec: &mut Vec<ChartCode>) -> Vec<(f64, f64)> {
-    code_vec.method_1(2.0);
-    code_vec
-        .iter()
-        .map(|ele| ele.method_2)
-        .dedup_by(|a, b| (a - b).abs() < 2.0)
-        .chain([f64::MAX])
-        .tuple_windows::<(_, _)>()
-        .collect()
+/// 字形與字源的配對結果，未能配對的元素單獨列出
+#[derive(Debug, Default)]
+pub struct Pairing<'a, 'b> {
+    pub pairs: Vec<(&'a ChartGraph<'a>, &'b ChartSource)>,
+    pub graphs: Vec<&'a ChartGraph<'a>>,
+    pub sources: Vec<&'b ChartSource>,
 }
 
-#[duplicate_item(
-    [
-        name [group_graph_source_iter_by_col]
-        arg [row_ranges]
-        method_1 [x_min]
-    ]
-    [
-        name [group_graph_source_iter_by_row]
-        arg [col_ranges]
-        method_1 [y]
-    ]
-)]
-pub fn name<'a, 'b>(
-    graph_source_iter: impl Iterator<Item = (&'a ChartGraph<'a>, &'b ChartSource)>,
-    arg: &Vec<(f64, f64)>,
-) -> Vec<Vec<(&'a ChartGraph<'a>, &'b ChartSource)>> {
-    let mut res_dic = HashMap::new();
-
-    'outer: for (graph, source) in graph_source_iter {
-        for (k, (start, end)) in arg.iter().enumerate() {
-            if source.method_1 > *start && source.method_1 < *end {
-                let tmp = res_dic.entry(k).or_insert(Vec::new());
-                tmp.push((graph, source));
-                continue 'outer;
-            }
+/// 依位置爲每個字形配對其正下方、同一列內的字源：
+/// 字源須在字形基線之下一個字號加行距之內，且與字形的橫向中點相差不超過一個字號。
+/// 所有候選按距離從近到遠依次配對，每個字形與字源至多使用一次；結果按字源的順序排列
+pub fn pair_graph_source<'a, 'b>(
+    graph_vec: &'a [ChartGraph<'a>],
+    source_vec: &'b [ChartSource],
+    layout: &LayoutProfile,
+) -> Pairing<'a, 'b> {
+    let candidates = graph_vec
+        .iter()
+        .enumerate()
+        .cartesian_product(source_vec.iter().enumerate())
+        .filter_map(|((g, graph), (s, source))| {
+            let dy = source.y - graph.y;
+            let dx = ((source.x_min + source.x_max) / 2.0 - graph.center_x()).abs();
+            (dy > 0.0 && dy <= graph.size + layout.row_gap && dx <= graph.size).then_some((
+                dx + dy,
+                g,
+                s,
+            ))
+        })
+        .sorted_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut graph_used = vec![false; graph_vec.len()];
+    let mut source_pair = vec![None; source_vec.len()];
+    for (_, g, s) in candidates {
+        if !graph_used[g] && source_pair[s].is_none() {
+            graph_used[g] = true;
+            source_pair[s] = Some(g);
         }
+    }
 
-        let tmp = res_dic.entry(arg.len()).or_insert(Vec::new());
-        tmp.push((graph, source));
+    let mut pairing = Pairing::default();
+    for (source, pair) in source_vec.iter().zip(source_pair) {
+        match pair {
+            Some(g) => pairing.pairs.push((&graph_vec[g], source)),
+            None => pairing.sources.push(source),
+        }
     }
-    let res = res_dic
-        .into_iter()
-        .sorted_by_key(|&(a, _)| a)
-        .filter(|(_, b)| !b.is_empty())
-        .map(|(_, b)| b)
-        .collect_vec();
-
-    res
+    pairing.graphs = graph_vec
+        .iter()
+        .zip(graph_used)
+        .filter(|(_, used)| !used)
+        .map(|(graph, _)| graph)
+        .collect();
+
+    pairing
 }
 
-pub fn split_code_vec(code_vec: &mut Vec<ChartCode>) -> Vec<Vec<ChartCode>> {
-    code_vec.sort_x_y(2.0);
-    let mut res = vec![];
-    for (_, v) in &code_vec.iter().group_by(|&ele| ele.x_min) {
-        let tmp = v.map(|&each| each).collect_vec();
-        res.push(tmp);
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::page_source::{MemoryPageSource, PageSource};
+
+    const PAGE_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="612" height="792" viewBox="0 0 612 792">
+<defs>
+<path id="font_0_1" d="M0 .5L1 .5"/>
+<path id="font_1_2" d="M0 0L.5 0"/>
+</defs>
+<g>
+<use data-text="4" xlink:href="#font_1_2" transform="matrix(9.9998,0,0,-9.9998,50,100)"/>
+<use data-text="E" xlink:href="#font_1_2" transform="matrix(9.9998,0,0,-9.9998,56,100)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(9.9998,0,0,-9.9998,62,100)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(9.9998,0,0,-9.9998,68,100)"/>
+<use data-text="&#x4e00;" xlink:href="#font_0_1" transform="matrix(22,0,0,-22,120,130)"/>
+<use data-text="G" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,118,145)"/>
+<use data-text="0" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,121.5,145)"/>
+<use data-text="-" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,125,145)"/>
+<use data-text="5" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,127,145)"/>
+<use data-text="2" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,130.5,145)"/>
+<use data-text="3" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,134,145)"/>
+<use data-text="B" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,137.5,145)"/>
+</g>
+</svg>
+"##;
+
+    #[test]
+    fn parse_memory_page() {
+        let doc = MemoryPageSource::new([PAGE_SVG]);
+        let page_svg = doc.page_svg(0).unwrap();
+
+        let font_dic = parse_font_drawn(&page_svg).unwrap();
+        let detail_list = parse_details(&page_svg).unwrap();
+        assert_eq!(font_dic.len(), 2);
+        assert_eq!(detail_list.len(), 12);
+
+        let layout = LayoutProfile::default();
+        let code_vec = ChartCode::new(&detail_list, &layout).unwrap();
+        let source_vec = ChartSource::new(&detail_list, &layout);
+        let graph_vec =
+            ChartGraph::new(&detail_list, &font_dic, &(0x4e00..=0x9fff), &layout).unwrap();
+
+        assert_eq!(code_vec.len(), 1);
+        assert_eq!(code_vec[0].hex(), "4E00");
+        assert_eq!(source_vec.len(), 1);
+        assert_eq!(source_vec[0].source, "G0-523B");
+        assert_eq!(graph_vec.len(), 1);
+        assert_eq!(graph_vec[0].ch, '一');
+        assert_eq!(graph_vec[0].drawn, "M0 .5L1 .5");
+    }
+
+    #[test]
+    fn parse_xml_variants() {
+        let svg = r##"<svg xmlns:xlink="http://www.w3.org/1999/xlink">
+<defs>
+<symbol id="font_0_1" viewBox="0 0 1 1"><path d="M0 .5L1 .5"/></symbol>
+<path d="M0&#32;0L.5 0" id="font_1&amp;2"></path>
+<clipPath id="clip"><use data-text="x" xlink:href="#font_1&amp;2"/></clipPath>
+</defs>
+<g transform="translate(0,792)">
+<g transform="matrix(1 0 0 -1 10 0)">
+<use transform="matrix(22,0,0,-22,120,130)" xlink:href="#font_0_1" data-text="&#19968;"/>
+<use href="#font_1&amp;2" data-text="&amp;"/>
+</g>
+</g>
+<g><use data-text=" " xlink:href="#font_1&amp;2" x="5" y="6"></use></g>
+</svg>"##;
+
+        // 屬性值均經反轉義
+        let font_dic = parse_font_drawn(svg).unwrap();
+        assert_eq!(font_dic["font_0_1"], "M0 .5L1 .5");
+        assert_eq!(font_dic["font_1&2"], "M0 0L.5 0");
+
+        let detail_list = parse_details(svg).unwrap();
+        assert_eq!(
+            detail_list,
+            vec![
+                (
+                    "一".to_string(),
+                    "font_0_1".to_string(),
+                    vec![22.0, 0.0, 0.0, 22.0, 130.0, 662.0]
+                ),
+                (
+                    "&".to_string(),
+                    "font_1&2".to_string(),
+                    vec![1.0, 0.0, 0.0, -1.0, 10.0, 792.0]
+                ),
+                (
+                    String::new(),
+                    "font_1&2".to_string(),
+                    vec![1.0, 0.0, 0.0, 1.0, 5.0, 6.0]
+                ),
+            ]
+        );
     }
 
-    res
+    #[test]
+    fn pair_by_position() {
+        // 多出一個註腳字形，另有一個沒有字形的字源
+        let page_svg = PAGE_SVG.replace(
+            "</g>",
+            r##"<use data-text="&#x4e01;" xlink:href="#font_0_1" transform="matrix(22,0,0,-22,300,700)"/>
+<use data-text="T" xlink:href="#font_1_2" transform="matrix(6,0,0,-6,400,145)"/>
+</g>"##,
+        );
+        let layout = LayoutProfile::default();
+        let font_dic = parse_font_drawn(&page_svg).unwrap();
+        let detail_list = parse_details(&page_svg).unwrap();
+        let source_vec = ChartSource::new(&detail_list, &layout);
+        let graph_vec =
+            ChartGraph::new(&detail_list, &font_dic, &(0x4e00..=0x9fff), &layout).unwrap();
+
+        let pairing = pair_graph_source(&graph_vec, &source_vec, &layout);
+        assert_eq!(pairing.pairs.len(), 1);
+        assert_eq!(pairing.pairs[0].0.ch, '一');
+        assert_eq!(pairing.pairs[0].1.source, "G0-523B");
+        assert_eq!(pairing.graphs.len(), 1);
+        assert_eq!(pairing.graphs[0].ch, '丁');
+        assert_eq!(pairing.sources.len(), 1);
+        assert_eq!(pairing.sources[0].source, "T");
+    }
+
+    #[test]
+    fn unrecognized_source() {
+        // 未知地區的字源只略去該字形，不影響整頁
+        let page_svg = PAGE_SVG.replace(r#"data-text="G""#, r#"data-text="Q""#);
+        let page =
+            crate::chart::parse_page(&page_svg, 1, &(0x4e00..=0x9fff), &LayoutProfile::default())
+                .unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert!(page.entries[0].glyphs.is_empty());
+        assert!(matches!(
+            &page.unpaired[..],
+            [crate::chart::Unpaired::Unrecognized { character: '一', source, .. }] if source == "Q0-523B"
+        ));
+        assert_eq!(page.unpaired[0].codepoints(), [0x4E00]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(matches!(
+            parse_font_drawn("<svg><g></g></svg>"),
+            Err(Error::MissingElement("defs"))
+        ));
+
+        let bad_matrix = PAGE_SVG.replace("matrix(22,0,0,-22,120,130)", "matrix(22,0,0)");
+        assert!(matches!(
+            parse_details(&bad_matrix),
+            Err(Error::MalformedMatrix(_))
+        ));
+
+        let unknown_font = PAGE_SVG.replace(r##"href="#font_0_1""##, r##"href="#font_9_9""##);
+        let font_dic = parse_font_drawn(&unknown_font).unwrap();
+        let detail_list = parse_details(&unknown_font).unwrap();
+        assert!(matches!(
+            ChartGraph::new(
+                &detail_list,
+                &font_dic,
+                &(0x4e00..=0x9fff),
+                &LayoutProfile::default()
+            ),
+            Err(Error::UnknownFont(_))
+        ));
+    }
 }
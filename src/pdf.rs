@@ -0,0 +1,1839 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt::Write,
+    ops::RangeInclusive,
+    path::Path,
+    rc::Rc,
+};
+
+use lopdf::{content::Content, decode_text_string, Dictionary, Document, Object, ObjectId};
+use ttf_parser::{GlyphId, OutlineBuilder};
+
+use crate::{
+    error::{Error, Result},
+    page_source::{DocumentMetadata, PageSource},
+};
+
+// 嵌套 Form XObject 的最大深度
+const MAX_FORM_DEPTH: usize = 16;
+
+// CMap 與 /W 中單個範圍最多包含的碼位數，超出者視爲損壞而忽略
+const MAX_RANGE_SPAN: u32 = 0x10000;
+
+/// 純 Rust 的 PDF 後端，輸出與 PyMuPDF `get_svg_image` 相同結構的 SVG
+pub struct PdfDocument {
+    doc: Document,
+    page_ids: Vec<ObjectId>,
+    fonts: RefCell<HashMap<ObjectId, Rc<PdfFont>>>,
+}
+
+impl PdfDocument {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let doc = Document::load(&path)
+            .map_err(|err| Error::Pdf(format!("無法打開 {}: {}", path.as_ref().display(), err)))?;
+        Ok(Self::from_document(doc))
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let doc = Document::load_mem(data)?;
+        Ok(Self::from_document(doc))
+    }
+
+    fn from_document(doc: Document) -> Self {
+        let page_ids = doc.get_pages().into_values().collect();
+        Self {
+            doc,
+            page_ids,
+            fonts: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn deref<'a>(&'a self, obj: &'a Object) -> &'a Object {
+        match self.doc.dereference(obj) {
+            Ok((_, obj)) => obj,
+            Err(_) => obj,
+        }
+    }
+
+    fn inherited<'a>(&'a self, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+        let mut node = dict;
+        for _ in 0..MAX_FORM_DEPTH {
+            if let Ok(obj) = node.get(key) {
+                return Some(obj);
+            }
+            node = node
+                .get(b"Parent")
+                .ok()
+                .and_then(|parent| self.deref(parent).as_dict().ok())?;
+        }
+        None
+    }
+
+    fn numbers(&self, obj: &Object) -> Option<Vec<f64>> {
+        self.deref(obj)
+            .as_array()
+            .ok()?
+            .iter()
+            .map(|each| number(self.deref(each)))
+            .collect()
+    }
+
+    fn stream_data(&self, obj: &Object) -> Option<Vec<u8>> {
+        self.deref(obj).as_stream().ok()?.get_plain_content().ok()
+    }
+
+    fn load_font(&self, obj: &Object) -> Result<Rc<PdfFont>> {
+        let id = obj.as_reference().ok();
+        if let Some(font) = id.and_then(|id| self.fonts.borrow().get(&id).cloned()) {
+            return Ok(font);
+        }
+
+        let index = self.fonts.borrow().len();
+        let dict = self.deref(obj).as_dict()?;
+        let font = Rc::new(PdfFont::load(self, dict, index)?);
+        if let Some(id) = id {
+            self.fonts.borrow_mut().insert(id, font.clone());
+        }
+
+        Ok(font)
+    }
+}
+
+impl PageSource for PdfDocument {
+    fn page_count(&self) -> usize {
+        self.page_ids.len()
+    }
+
+    fn page_svg(&self, page_idx: usize) -> Result<String> {
+        let page_id = *self
+            .page_ids
+            .get(page_idx)
+            .ok_or_else(|| Error::Invalid(format!("頁碼 {} 超出範圍", page_idx)))?;
+        let page = self.doc.get_dictionary(page_id)?;
+
+        let media_box = self
+            .inherited(page, b"CropBox")
+            .or_else(|| self.inherited(page, b"MediaBox"))
+            .and_then(|obj| self.numbers(obj))
+            .filter(|nums| nums.len() == 4)
+            .unwrap_or_else(|| vec![0.0, 0.0, 612.0, 792.0]);
+        let (x0, y0) = (
+            media_box[0].min(media_box[2]),
+            media_box[1].min(media_box[3]),
+        );
+        let (x1, y1) = (
+            media_box[0].max(media_box[2]),
+            media_box[1].max(media_box[3]),
+        );
+
+        // PDF 座標原點在左下，SVG 在左上
+        let mut renderer = PageRenderer {
+            pdf: self,
+            page_matrix: Matrix::new(1.0, 0.0, 0.0, -1.0, -x0, y1),
+            defs: String::new(),
+            uses: String::new(),
+            defined: HashSet::new(),
+            actual_text: vec![],
+        };
+
+        let empty = Dictionary::new();
+        let resources = self
+            .inherited(page, b"Resources")
+            .and_then(|obj| self.deref(obj).as_dict().ok())
+            .unwrap_or(&empty);
+        let content = Content::decode(&self.doc.get_page_content(page_id))?;
+        renderer.run(&content.operations, resources, GraphicsState::default(), 0)?;
+
+        let (width, height) = (x1 - x0, y1 - y0);
+        Ok(format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.1" width="{w}" height="{h}" viewBox="0 0 {w} {h}">
+<defs>
+{defs}</defs>
+<g>
+{uses}</g>
+</svg>
+"#,
+            w = fmt_num(width),
+            h = fmt_num(height),
+            defs = renderer.defs,
+            uses = renderer.uses,
+        ))
+    }
+
+    fn metadata(&self) -> DocumentMetadata {
+        let info = self
+            .doc
+            .trailer
+            .get(b"Info")
+            .ok()
+            .and_then(|obj| self.deref(obj).as_dict().ok());
+        let field = |key: &[u8]| {
+            info.and_then(|dict| dict.get(key).ok())
+                .and_then(|obj| decode_text_string(self.deref(obj)).ok())
+                .filter(|text| !text.is_empty())
+        };
+
+        DocumentMetadata {
+            title: field(b"Title"),
+            author: field(b"Author"),
+            subject: field(b"Subject"),
+            creator: field(b"Creator"),
+            producer: field(b"Producer"),
+            creation_date: field(b"CreationDate"),
+            modification_date: field(b"ModDate"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Matrix {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+    f: f64,
+}
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+
+    const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    fn from_slice(nums: &[f64]) -> Option<Self> {
+        match nums {
+            &[a, b, c, d, e, f] => Some(Self::new(a, b, c, d, e, f)),
+            _ => None,
+        }
+    }
+
+    fn translate(x: f64, y: f64) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    // 依 PDF 的行向量慣例，`self` 先作用，`other` 後作用
+    fn then(&self, other: &Matrix) -> Matrix {
+        Matrix::new(
+            self.a * other.a + self.b * other.c,
+            self.a * other.b + self.b * other.d,
+            self.c * other.a + self.d * other.c,
+            self.c * other.b + self.d * other.d,
+            self.e * other.a + self.f * other.c + other.e,
+            self.e * other.b + self.f * other.d + other.f,
+        )
+    }
+
+    fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x * self.a + y * self.c + self.e,
+            x * self.b + y * self.d + self.f,
+        )
+    }
+}
+
+fn number(obj: &Object) -> Option<f64> {
+    match obj {
+        Object::Integer(num) => Some(*num as f64),
+        Object::Real(num) => Some(*num as f64),
+        _ => None,
+    }
+}
+
+// 以 f32 的最短表示輸出，使 9.9998 之類的字號保持原樣
+fn fmt_num(num: f64) -> String {
+    let num = num as f32;
+    if num == 0.0 {
+        "0".to_string()
+    } else {
+        format!("{}", num)
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ch.is_ascii_graphic() && !"<>&\"'".contains(ch) => ch.to_string(),
+        (Some(ch), None) if ch != ' ' => format!("&#x{:x};", ch as u32),
+        _ => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    }
+}
+
+#[derive(Clone)]
+struct GraphicsState {
+    ctm: Matrix,
+    font: Option<Rc<PdfFont>>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    h_scale: f64,
+    leading: f64,
+    rise: f64,
+    render_mode: i64,
+}
+
+impl Default for GraphicsState {
+    fn default() -> Self {
+        Self {
+            ctm: Matrix::IDENTITY,
+            font: None,
+            font_size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            h_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+            render_mode: 0,
+        }
+    }
+}
+
+struct PageRenderer<'a> {
+    pdf: &'a PdfDocument,
+    page_matrix: Matrix,
+    defs: String,
+    uses: String,
+    defined: HashSet<String>,
+    // 每層標記內容的 ActualText，以及已分配出去的字數
+    actual_text: Vec<Option<(Vec<char>, usize)>>,
+}
+
+impl<'a> PageRenderer<'a> {
+    fn run(
+        &mut self,
+        operations: &[lopdf::content::Operation],
+        resources: &'a Dictionary,
+        mut gs: GraphicsState,
+        depth: usize,
+    ) -> Result<()> {
+        let mut stack = vec![];
+        let mut tm = Matrix::IDENTITY;
+        let mut tlm = Matrix::IDENTITY;
+
+        for op in operations {
+            let nums = op.operands.iter().filter_map(number).collect::<Vec<_>>();
+
+            match op.operator.as_str() {
+                "q" => stack.push(gs.clone()),
+                "Q" => {
+                    if let Some(prev) = stack.pop() {
+                        gs = prev;
+                    }
+                }
+                "cm" => {
+                    if let Some(m) = Matrix::from_slice(&nums) {
+                        gs.ctm = m.then(&gs.ctm);
+                    }
+                }
+                "BT" => {
+                    tm = Matrix::IDENTITY;
+                    tlm = Matrix::IDENTITY;
+                }
+                "Tf" => {
+                    let font_name = op.operands.first().and_then(|obj| obj.as_name().ok());
+                    if let (Some(font_name), Some(&size)) = (font_name, nums.first()) {
+                        gs.font = match self.resource(resources, b"Font", font_name) {
+                            Some(obj) => Some(self.pdf.load_font(obj).map_err(|err| {
+                                Error::Pdf(format!(
+                                    "無法載入字體 {}: {}",
+                                    String::from_utf8_lossy(font_name),
+                                    err
+                                ))
+                            })?),
+                            None => None,
+                        };
+                        gs.font_size = size;
+                    }
+                }
+                "Tc" => gs.char_spacing = nums.first().copied().unwrap_or(0.0),
+                "Tw" => gs.word_spacing = nums.first().copied().unwrap_or(0.0),
+                "Tz" => gs.h_scale = nums.first().copied().unwrap_or(100.0) / 100.0,
+                "TL" => gs.leading = nums.first().copied().unwrap_or(0.0),
+                "Ts" => gs.rise = nums.first().copied().unwrap_or(0.0),
+                "Tr" => gs.render_mode = nums.first().copied().unwrap_or(0.0) as i64,
+                "Td" | "TD" => {
+                    if let &[tx, ty] = &nums[..] {
+                        if op.operator == "TD" {
+                            gs.leading = -ty;
+                        }
+                        tlm = Matrix::translate(tx, ty).then(&tlm);
+                        tm = tlm;
+                    }
+                }
+                "Tm" => {
+                    if let Some(m) = Matrix::from_slice(&nums) {
+                        tlm = m;
+                        tm = m;
+                    }
+                }
+                "T*" => {
+                    tlm = Matrix::translate(0.0, -gs.leading).then(&tlm);
+                    tm = tlm;
+                }
+                "Tj" | "'" | "\"" => {
+                    if op.operator != "Tj" {
+                        if op.operator == "\"" {
+                            if let &[aw, ac] = &nums[..] {
+                                gs.word_spacing = aw;
+                                gs.char_spacing = ac;
+                            }
+                        }
+                        tlm = Matrix::translate(0.0, -gs.leading).then(&tlm);
+                        tm = tlm;
+                    }
+                    if let Some(Ok(bytes)) = op.operands.last().map(Object::as_str) {
+                        self.show_text(&gs, &mut tm, bytes);
+                    }
+                }
+                "TJ" => {
+                    if let Some(Ok(items)) = op.operands.first().map(Object::as_array) {
+                        for item in items {
+                            if let Ok(bytes) = item.as_str() {
+                                self.show_text(&gs, &mut tm, bytes);
+                            } else if let Some(adjust) = number(item) {
+                                let tx = -adjust / 1000.0 * gs.font_size * gs.h_scale;
+                                tm = Matrix::translate(tx, 0.0).then(&tm);
+                            }
+                        }
+                    }
+                }
+                "BMC" => self.actual_text.push(None),
+                "BDC" => {
+                    let props = match op.operands.get(1) {
+                        Some(Object::Name(name)) => self.resource(resources, b"Properties", name),
+                        other => other,
+                    };
+                    let text = props
+                        .and_then(|obj| self.pdf.deref(obj).as_dict().ok())
+                        .and_then(|dict| dict.get(b"ActualText").ok())
+                        .and_then(|obj| decode_text_string(self.pdf.deref(obj)).ok())
+                        .map(|text| (text.chars().collect(), 0));
+                    self.actual_text.push(text);
+                }
+                "EMC" => {
+                    self.actual_text.pop();
+                }
+                "Do" if depth < MAX_FORM_DEPTH => {
+                    let Some(name) = op.operands.first().and_then(|obj| obj.as_name().ok()) else {
+                        continue;
+                    };
+                    let Some(Ok(stream)) = self
+                        .resource(resources, b"XObject", name)
+                        .map(|obj| self.pdf.deref(obj).as_stream())
+                    else {
+                        continue;
+                    };
+                    if stream.dict.get(b"Subtype").and_then(Object::as_name).ok() != Some(b"Form") {
+                        continue;
+                    }
+
+                    let mut form_gs = gs.clone();
+                    if let Some(m) = stream
+                        .dict
+                        .get(b"Matrix")
+                        .ok()
+                        .and_then(|obj| self.pdf.numbers(obj))
+                        .and_then(|nums| Matrix::from_slice(&nums))
+                    {
+                        form_gs.ctm = m.then(&gs.ctm);
+                    }
+                    let form_resources = stream
+                        .dict
+                        .get(b"Resources")
+                        .ok()
+                        .and_then(|obj| self.pdf.deref(obj).as_dict().ok())
+                        .unwrap_or(resources);
+                    let content = Content::decode(&stream.get_plain_content()?)?;
+                    self.run(&content.operations, form_resources, form_gs, depth + 1)?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resource(
+        &self,
+        resources: &'a Dictionary,
+        category: &[u8],
+        name: &[u8],
+    ) -> Option<&'a Object> {
+        let pdf = self.pdf;
+        resources
+            .get(category)
+            .ok()
+            .and_then(|obj| pdf.deref(obj).as_dict().ok())
+            .and_then(|dict| dict.get(name).ok())
+    }
+
+    fn next_actual_char(&mut self) -> Option<String> {
+        let (chars, used) = self.actual_text.iter_mut().rev().flatten().next()?;
+        let res = chars
+            .get(*used)
+            .map(|ch| ch.to_string())
+            .unwrap_or_default();
+        *used += 1;
+
+        Some(res)
+    }
+
+    fn show_text(&mut self, gs: &GraphicsState, tm: &mut Matrix, bytes: &[u8]) {
+        let Some(font) = gs.font.clone() else {
+            return;
+        };
+
+        for (code, len) in font.split_codes(bytes) {
+            let trm = Matrix::new(
+                gs.font_size * gs.h_scale,
+                0.0,
+                0.0,
+                gs.font_size,
+                0.0,
+                gs.rise,
+            )
+            .then(tm)
+            .then(&gs.ctm)
+            .then(&self.page_matrix);
+
+            // 模式 3 爲不可見文字
+            if gs.render_mode != 3 {
+                let text = self
+                    .next_actual_char()
+                    .or_else(|| font.unicode(code))
+                    .unwrap_or_else(|| '\u{FFFD}'.to_string());
+                let (key, drawn) = font.glyph(code);
+                let id = format!("font_{}_{}", font.index, key);
+                if self.defined.insert(id.clone()) {
+                    writeln!(self.defs, r#"<path id="{}" d="{}"/>"#, id, drawn).unwrap();
+                }
+                writeln!(
+                    self.uses,
+                    r##"<use data-text="{}" xlink:href="#{}" transform="matrix({},{},{},{},{},{})" fill="#000000"/>"##,
+                    escape_text(&text),
+                    id,
+                    fmt_num(trm.a),
+                    fmt_num(trm.b),
+                    fmt_num(trm.c),
+                    fmt_num(trm.d),
+                    fmt_num(trm.e),
+                    fmt_num(trm.f),
+                )
+                .unwrap();
+            }
+
+            let word_spacing = if len == 1 && code == 32 {
+                gs.word_spacing
+            } else {
+                0.0
+            };
+            let tx =
+                (font.width(code) * gs.font_size + gs.char_spacing + word_spacing) * gs.h_scale;
+            *tm = Matrix::translate(tx, 0.0).then(tm);
+        }
+    }
+}
+
+enum FontProgram {
+    // TrueType 或 OpenType 包裝的字體
+    Sfnt(Vec<u8>),
+    // 裸 CFF（Type1C、CIDFontType0C）
+    Cff(Vec<u8>),
+    Type3 {
+        procs: HashMap<Vec<u8>, Vec<u8>>,
+        matrix: Matrix,
+    },
+    None,
+}
+
+enum Widths {
+    Simple {
+        first: u32,
+        widths: Vec<f64>,
+        missing: f64,
+    },
+    Cid {
+        default: f64,
+        widths: HashMap<u32, f64>,
+    },
+}
+
+struct PdfFont {
+    index: usize,
+    composite: bool,
+    type3: bool,
+    widths: Widths,
+    // 簡單字體的編碼差異表：code -> glyph name
+    differences: HashMap<u32, Vec<u8>>,
+    // 簡單字體依 /Encoding 得到的 Unicode
+    simple_unicode: HashMap<u32, String>,
+    to_unicode: CMap,
+    encoding_cmap: Option<CMap>,
+    cid_to_gid: Option<Vec<u16>>,
+    // CFF CID 字體：CID -> 字形號
+    cid_gids: Option<HashMap<u32, u16>>,
+    // 簡單字體：code -> 字形號
+    code_gids: HashMap<u32, u16>,
+    // 字形號（Type3 字體爲 code） -> 以 em 爲單位的路徑
+    outlines: HashMap<u32, String>,
+}
+
+impl PdfFont {
+    fn load(pdf: &PdfDocument, dict: &Dictionary, index: usize) -> Result<Self> {
+        let subtype = dict
+            .get(b"Subtype")
+            .and_then(Object::as_name)
+            .unwrap_or(b"");
+        let composite = subtype == b"Type0";
+
+        let to_unicode = dict
+            .get(b"ToUnicode")
+            .ok()
+            .and_then(|obj| pdf.stream_data(obj))
+            .map(|data| CMap::parse(&data))
+            .unwrap_or_default();
+
+        let mut font = PdfFont {
+            index,
+            composite,
+            type3: false,
+            widths: Widths::Simple {
+                first: 0,
+                widths: vec![],
+                missing: 0.0,
+            },
+            differences: HashMap::new(),
+            simple_unicode: HashMap::new(),
+            to_unicode,
+            encoding_cmap: None,
+            cid_to_gid: None,
+            cid_gids: None,
+            code_gids: HashMap::new(),
+            outlines: HashMap::new(),
+        };
+        let mut program = FontProgram::None;
+
+        if composite {
+            font.encoding_cmap = dict
+                .get(b"Encoding")
+                .ok()
+                .and_then(|obj| pdf.stream_data(obj))
+                .map(|data| CMap::parse(&data));
+
+            let descendant = dict
+                .get(b"DescendantFonts")
+                .ok()
+                .and_then(|obj| pdf.deref(obj).as_array().ok())
+                .and_then(|arr| arr.first())
+                .and_then(|obj| pdf.deref(obj).as_dict().ok())
+                .ok_or_else(|| Error::Pdf("Type0 字體缺少 DescendantFonts".to_string()))?;
+
+            font.widths = Widths::Cid {
+                default: descendant
+                    .get(b"DW")
+                    .ok()
+                    .and_then(|obj| number(pdf.deref(obj)))
+                    .unwrap_or(1000.0)
+                    / 1000.0,
+                widths: parse_cid_widths(pdf, descendant),
+            };
+            font.cid_to_gid = descendant
+                .get(b"CIDToGIDMap")
+                .ok()
+                .and_then(|obj| pdf.stream_data(obj))
+                .map(|data| {
+                    data.chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .collect()
+                });
+            program = load_font_program(pdf, descendant);
+        } else {
+            let first = dict
+                .get(b"FirstChar")
+                .ok()
+                .and_then(|obj| number(pdf.deref(obj)))
+                .unwrap_or(0.0) as u32;
+            let widths = dict
+                .get(b"Widths")
+                .ok()
+                .and_then(|obj| pdf.numbers(obj))
+                .unwrap_or_default();
+
+            let mut width_scale = 0.001;
+            if subtype == b"Type3" {
+                let matrix = dict
+                    .get(b"FontMatrix")
+                    .ok()
+                    .and_then(|obj| pdf.numbers(obj))
+                    .and_then(|nums| Matrix::from_slice(&nums))
+                    .unwrap_or(Matrix::new(0.001, 0.0, 0.0, 0.001, 0.0, 0.0));
+                width_scale = matrix.a;
+
+                let mut procs = HashMap::new();
+                if let Some(dict) = dict
+                    .get(b"CharProcs")
+                    .ok()
+                    .and_then(|obj| pdf.deref(obj).as_dict().ok())
+                {
+                    for (name, obj) in dict.iter() {
+                        if let Some(data) = pdf.stream_data(obj) {
+                            procs.insert(name.clone(), data);
+                        }
+                    }
+                }
+                program = FontProgram::Type3 { procs, matrix };
+            } else if let Some(descriptor) = dict
+                .get(b"FontDescriptor")
+                .ok()
+                .and_then(|obj| pdf.deref(obj).as_dict().ok())
+            {
+                program = load_font_program(pdf, dict);
+                if let FontProgram::None = program {
+                    program = load_font_program(pdf, descriptor);
+                }
+            }
+
+            font.widths = Widths::Simple {
+                first,
+                widths: widths.iter().map(|w| w * width_scale).collect(),
+                missing: dict
+                    .get(b"FontDescriptor")
+                    .ok()
+                    .and_then(|obj| pdf.deref(obj).as_dict().ok())
+                    .and_then(|desc| desc.get(b"MissingWidth").ok())
+                    .and_then(|obj| number(pdf.deref(obj)))
+                    .unwrap_or(0.0)
+                    * width_scale,
+            };
+
+            if let Some(Ok(encoding)) = dict
+                .get(b"Encoding")
+                .ok()
+                .map(|obj| pdf.deref(obj).as_dict())
+            {
+                if let Ok(diffs) = encoding.get(b"Differences").and_then(Object::as_array) {
+                    let mut code = 0;
+                    for item in diffs {
+                        match pdf.deref(item) {
+                            Object::Integer(num) => code = *num as u32,
+                            Object::Name(name) => {
+                                font.differences.insert(code, name.clone());
+                                code += 1;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+
+            if let Ok(encoding) = dict.get_font_encoding(&pdf.doc) {
+                for code in 0..=255u8 {
+                    if let Ok(text) = encoding.bytes_to_string(&[code]) {
+                        if !text.is_empty() {
+                            font.simple_unicode.insert(code as u32, text);
+                        }
+                    }
+                }
+            }
+        }
+        font.load_glyphs(program)?;
+
+        Ok(font)
+    }
+
+    fn split_codes(&self, bytes: &[u8]) -> Vec<(u32, usize)> {
+        if !self.composite {
+            return bytes.iter().map(|&b| (b as u32, 1)).collect();
+        }
+
+        let codespace = match &self.encoding_cmap {
+            Some(cmap) if !cmap.codespace.is_empty() => &cmap.codespace,
+            _ => &self.to_unicode.codespace,
+        };
+
+        let mut res = vec![];
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let mut len = (1..=4)
+                .find(|&len| {
+                    pos + len <= bytes.len()
+                        && codespace.iter().any(|&(cs_len, lo, hi)| {
+                            let code = be_code(&bytes[pos..pos + len]);
+                            cs_len == len && code >= lo && code <= hi
+                        })
+                })
+                .unwrap_or(2);
+            len = len.min(bytes.len() - pos);
+            res.push((be_code(&bytes[pos..pos + len]), len));
+            pos += len;
+        }
+
+        res
+    }
+
+    fn cid(&self, code: u32) -> u32 {
+        self.encoding_cmap
+            .as_ref()
+            .and_then(|cmap| cmap.cid(code))
+            .unwrap_or(code)
+    }
+
+    fn unicode(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.to_unicode.unicode.get(&code) {
+            return Some(text.clone());
+        }
+        if self.composite {
+            return None;
+        }
+        if let Some(ch) = self
+            .differences
+            .get(&code)
+            .and_then(|name| glyph_name_to_char(name))
+        {
+            return Some(ch.to_string());
+        }
+
+        self.simple_unicode.get(&code).cloned()
+    }
+
+    fn width(&self, code: u32) -> f64 {
+        match &self.widths {
+            Widths::Simple {
+                first,
+                widths,
+                missing,
+            } => code
+                .checked_sub(*first)
+                .and_then(|idx| widths.get(idx as usize))
+                .copied()
+                .unwrap_or(*missing),
+            Widths::Cid { default, widths } => {
+                widths.get(&self.cid(code)).copied().unwrap_or(*default)
+            }
+        }
+    }
+
+    // 返回 (字形鍵, 以 em 爲單位的路徑)
+    fn glyph(&self, code: u32) -> (u32, &str) {
+        let key = match self.type3 {
+            true => code,
+            false => self.glyph_id(code).unwrap_or(0) as u32,
+        };
+        let drawn = self.outlines.get(&key).map(String::as_str).unwrap_or("");
+
+        (key, drawn)
+    }
+
+    fn glyph_id(&self, code: u32) -> Option<u16> {
+        if !self.composite {
+            return self.code_gids.get(&code).copied();
+        }
+
+        let cid = self.cid(code);
+        match (&self.cid_gids, &self.cid_to_gid) {
+            (Some(gids), _) => gids.get(&cid).copied(),
+            (None, Some(map)) => map.get(cid as usize).copied(),
+            (None, None) => Some(cid as u16),
+        }
+    }
+
+    // 字體程序只解析一次：算出各碼位（簡單字體）或 CID（CFF CID 字體）對應的字形號，並取出用得到的輪廓
+    fn load_glyphs(&mut self, program: FontProgram) -> Result<()> {
+        match program {
+            FontProgram::Sfnt(data) => {
+                let Ok(face) = ttf_parser::Face::parse(&data, 0) else {
+                    return Ok(());
+                };
+                let gids = match self.composite {
+                    true => (0..face.number_of_glyphs()).collect(),
+                    false => {
+                        for code in 0..=255 {
+                            if let Some(id) = self.sfnt_glyph_id(&face, code) {
+                                self.code_gids.insert(code, id);
+                            }
+                        }
+                        self.code_gids
+                            .values()
+                            .copied()
+                            .chain([0])
+                            .collect::<Vec<_>>()
+                    }
+                };
+                let scale = 1.0 / face.units_per_em() as f64;
+                for gid in gids {
+                    let mut writer = PathWriter::new(Matrix::new(scale, 0.0, 0.0, scale, 0.0, 0.0));
+                    face.outline_glyph(GlyphId(gid), &mut writer);
+                    self.outlines.insert(gid as u32, writer.finish());
+                }
+            }
+            FontProgram::Cff(data) => {
+                let Some(table) = ttf_parser::cff::Table::parse(&data) else {
+                    return Ok(());
+                };
+                let count = table.number_of_glyphs();
+                if self.composite {
+                    // CID 字體的字形號須經字符集查得
+                    if table.glyph_cid(GlyphId(0)).is_some() {
+                        self.cid_gids = Some(
+                            (0..count)
+                                .filter_map(|gid| {
+                                    Some((table.glyph_cid(GlyphId(gid))? as u32, gid))
+                                })
+                                .collect(),
+                        );
+                    }
+                } else {
+                    for code in 0..=255 {
+                        let id = self
+                            .differences
+                            .get(&code)
+                            .and_then(|name| {
+                                table.glyph_index_by_name(std::str::from_utf8(name).ok()?)
+                            })
+                            .or_else(|| table.glyph_index(code as u8));
+                        if let Some(id) = id {
+                            self.code_gids.insert(code, id.0);
+                        }
+                    }
+                }
+
+                let m = table.matrix();
+                let matrix = Matrix::new(
+                    m.sx as f64,
+                    m.ky as f64,
+                    m.kx as f64,
+                    m.sy as f64,
+                    m.tx as f64,
+                    m.ty as f64,
+                );
+                for gid in 0..count {
+                    let mut writer = PathWriter::new(matrix);
+                    let _ = table.outline(GlyphId(gid), &mut writer);
+                    self.outlines.insert(gid as u32, writer.finish());
+                }
+            }
+            FontProgram::Type3 { procs, matrix } => {
+                self.type3 = true;
+                for (&code, name) in &self.differences {
+                    let Some(data) = procs.get(name) else {
+                        continue;
+                    };
+                    let mut content = Content::decode(data).map_err(|err| {
+                        Error::Pdf(format!("無法解析 Type3 字形 {}: {}", code, err))
+                    })?;
+                    // lopdf 把 d0、d1 拆成 d 與下一操作的首個操作數，須還原
+                    for i in 1..content.operations.len() {
+                        let prev = &content.operations[i - 1];
+                        if prev.operator == "d"
+                            && prev.operands.iter().all(|obj| number(obj).is_some())
+                            && !content.operations[i].operands.is_empty()
+                        {
+                            content.operations[i].operands.remove(0);
+                        }
+                    }
+                    self.outlines
+                        .insert(code, type3_outline(&content.operations, matrix));
+                }
+            }
+            FontProgram::None => {}
+        }
+
+        Ok(())
+    }
+
+    fn sfnt_glyph_id(&self, face: &ttf_parser::Face, code: u32) -> Option<u16> {
+        if let Some(id) = self.differences.get(&code).and_then(|name| {
+            let name = std::str::from_utf8(name).ok()?;
+            face.glyph_index_by_name(name)
+                .or_else(|| face.glyph_index(glyph_name_to_char(name.as_bytes())?))
+        }) {
+            return Some(id.0);
+        }
+
+        let cmap = face.tables().cmap?;
+        for subtable in cmap.subtables {
+            let id = match (subtable.platform_id, subtable.encoding_id) {
+                (ttf_parser::PlatformId::Windows, 0) => subtable
+                    .glyph_index(0xF000 + code)
+                    .or_else(|| subtable.glyph_index(code)),
+                (ttf_parser::PlatformId::Macintosh, 0) => subtable.glyph_index(code),
+                _ => None,
+            };
+            if let Some(id) = id {
+                return Some(id.0);
+            }
+        }
+
+        self.unicode(code)
+            .and_then(|text| text.chars().next())
+            .and_then(|ch| face.glyph_index(ch))
+            .map(|id| id.0)
+    }
+}
+
+fn be_code(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u32)
+}
+
+fn load_font_program(pdf: &PdfDocument, dict: &Dictionary) -> FontProgram {
+    let Some(descriptor) = dict
+        .get(b"FontDescriptor")
+        .ok()
+        .and_then(|obj| pdf.deref(obj).as_dict().ok())
+    else {
+        return FontProgram::None;
+    };
+
+    if let Some(data) = descriptor
+        .get(b"FontFile2")
+        .ok()
+        .and_then(|obj| pdf.stream_data(obj))
+    {
+        return FontProgram::Sfnt(data);
+    }
+
+    if let Ok(Ok(stream)) = descriptor
+        .get(b"FontFile3")
+        .map(|obj| pdf.deref(obj).as_stream())
+    {
+        let Ok(data) = stream.get_plain_content() else {
+            return FontProgram::None;
+        };
+        return match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+            Ok(b"OpenType") => FontProgram::Sfnt(data),
+            _ => FontProgram::Cff(data),
+        };
+    }
+
+    // 未嵌入或 Type1（FontFile）字體：只保留文字與位置
+    FontProgram::None
+}
+
+fn parse_cid_widths(pdf: &PdfDocument, descendant: &Dictionary) -> HashMap<u32, f64> {
+    let mut res = HashMap::new();
+    let Some(items) = descendant
+        .get(b"W")
+        .ok()
+        .and_then(|obj| pdf.deref(obj).as_array().ok())
+    else {
+        return res;
+    };
+
+    let mut i = 0;
+    while i < items.len() {
+        let Some(first) = number(pdf.deref(&items[i])) else {
+            break;
+        };
+        match items.get(i + 1).map(|obj| pdf.deref(obj)) {
+            Some(Object::Array(widths)) => {
+                for (offset, w) in widths.iter().enumerate() {
+                    if let Some(w) = number(pdf.deref(w)) {
+                        res.insert(first as u32 + offset as u32, w / 1000.0);
+                    }
+                }
+                i += 2;
+            }
+            Some(last) => {
+                let (Some(last), Some(w)) = (
+                    number(last),
+                    items.get(i + 2).and_then(|obj| number(pdf.deref(obj))),
+                ) else {
+                    break;
+                };
+                let (first, last) = (first as u32, last as u32);
+                if last < first || last - first >= MAX_RANGE_SPAN {
+                    i += 3;
+                    continue;
+                }
+                for cid in first..=last {
+                    res.insert(cid, w / 1000.0);
+                }
+                i += 3;
+            }
+            None => break,
+        }
+    }
+
+    res
+}
+
+fn glyph_name_to_char(name: &[u8]) -> Option<char> {
+    let name = std::str::from_utf8(name).ok()?;
+    let hex = name
+        .strip_prefix("uni")
+        .filter(|hex| hex.len() == 4)
+        .or_else(|| {
+            name.strip_prefix('u')
+                .filter(|hex| (4..=6).contains(&hex.len()))
+        });
+    if let Some(hex) = hex {
+        return u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    }
+
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Some(ch),
+        _ => None,
+    }
+}
+
+fn type3_outline(operations: &[lopdf::content::Operation], font_matrix: Matrix) -> String {
+    let mut writer = PathWriter::new(font_matrix);
+    let mut ctm = Matrix::IDENTITY;
+    let mut stack = vec![];
+    let mut current = (0.0, 0.0);
+
+    for op in operations {
+        let nums = op.operands.iter().filter_map(number).collect::<Vec<_>>();
+        let pt = |x: f64, y: f64| ctm.apply(x, y);
+
+        match (op.operator.as_str(), &nums[..]) {
+            ("q", _) => stack.push(ctm),
+            ("Q", _) => ctm = stack.pop().unwrap_or(Matrix::IDENTITY),
+            ("cm", nums) => {
+                if let Some(m) = Matrix::from_slice(nums) {
+                    ctm = m.then(&ctm);
+                }
+            }
+            ("m", &[x, y]) => {
+                current = pt(x, y);
+                writer.move_to_f64(current.0, current.1);
+            }
+            ("l", &[x, y]) => {
+                current = pt(x, y);
+                writer.line_to_f64(current.0, current.1);
+            }
+            ("c", &[x1, y1, x2, y2, x, y]) => {
+                let (p1, p2) = (pt(x1, y1), pt(x2, y2));
+                current = pt(x, y);
+                writer.curve_to_f64(p1, p2, current);
+            }
+            ("v", &[x2, y2, x, y]) => {
+                let p2 = pt(x2, y2);
+                let p1 = current;
+                current = pt(x, y);
+                writer.curve_to_f64(p1, p2, current);
+            }
+            ("y", &[x1, y1, x, y]) => {
+                let p1 = pt(x1, y1);
+                current = pt(x, y);
+                writer.curve_to_f64(p1, current, current);
+            }
+            ("h", _) => writer.close(),
+            ("re", &[x, y, w, h]) => {
+                let (a, b, c, d) = (pt(x, y), pt(x + w, y), pt(x + w, y + h), pt(x, y + h));
+                writer.move_to_f64(a.0, a.1);
+                writer.line_to_f64(b.0, b.1);
+                writer.line_to_f64(c.0, c.1);
+                writer.line_to_f64(d.0, d.1);
+                writer.close();
+                current = a;
+            }
+            _ => {}
+        }
+    }
+
+    writer.finish()
+}
+
+struct PathWriter {
+    d: String,
+    matrix: Matrix,
+}
+
+impl PathWriter {
+    fn new(matrix: Matrix) -> Self {
+        Self {
+            d: String::new(),
+            matrix,
+        }
+    }
+
+    fn point(&mut self, x: f64, y: f64) {
+        let (x, y) = self.matrix.apply(x, y);
+        write!(self.d, "{} {}", fmt_num(x), fmt_num(y)).unwrap();
+    }
+
+    fn move_to_f64(&mut self, x: f64, y: f64) {
+        self.d.push('M');
+        self.point(x, y);
+    }
+
+    fn line_to_f64(&mut self, x: f64, y: f64) {
+        self.d.push('L');
+        self.point(x, y);
+    }
+
+    fn curve_to_f64(&mut self, p1: (f64, f64), p2: (f64, f64), p: (f64, f64)) {
+        self.d.push('C');
+        self.point(p1.0, p1.1);
+        self.d.push(' ');
+        self.point(p2.0, p2.1);
+        self.d.push(' ');
+        self.point(p.0, p.1);
+    }
+
+    fn finish(self) -> String {
+        self.d
+    }
+}
+
+impl OutlineBuilder for PathWriter {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.move_to_f64(x as f64, y as f64);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.line_to_f64(x as f64, y as f64);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.d.push('Q');
+        self.point(x1 as f64, y1 as f64);
+        self.d.push(' ');
+        self.point(x as f64, y as f64);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.curve_to_f64(
+            (x1 as f64, y1 as f64),
+            (x2 as f64, y2 as f64),
+            (x as f64, y as f64),
+        );
+    }
+
+    fn close(&mut self) {
+        self.d.push('Z');
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum CMapToken {
+    Hex(Vec<u8>),
+    Int(u32),
+    ArrayStart,
+    ArrayEnd,
+    Keyword(String),
+}
+
+/// ToUnicode 與嵌入的編碼 CMap
+#[derive(Default)]
+struct CMap {
+    // (字節數, 下限, 上限)
+    codespace: Vec<(usize, u32, u32)>,
+    unicode: HashMap<u32, String>,
+    // (下限, 上限, 起始 CID)
+    cid_ranges: Vec<(u32, u32, u32)>,
+}
+
+impl CMap {
+    fn parse(data: &[u8]) -> Self {
+        let tokens = tokenize_cmap(data);
+        let mut cmap = CMap::default();
+        let mut section = String::new();
+        let mut operands: Vec<&CMapToken> = vec![];
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                CMapToken::Keyword(kw) if kw.starts_with("begin") => {
+                    section = kw.clone();
+                    operands.clear();
+                }
+                CMapToken::Keyword(kw) if kw.starts_with("end") => section.clear(),
+                CMapToken::Keyword(_) => operands.clear(),
+                CMapToken::ArrayStart if section == "beginbfrange" && operands.len() == 2 => {
+                    let (CMapToken::Hex(lo), CMapToken::Hex(hi)) = (operands[0], operands[1])
+                    else {
+                        operands.clear();
+                        i += 1;
+                        continue;
+                    };
+                    let mut codes = cmap.code_range(lo, hi).into_iter().flatten();
+                    i += 1;
+                    while i < tokens.len() && tokens[i] != CMapToken::ArrayEnd {
+                        if let CMapToken::Hex(bytes) = &tokens[i] {
+                            // 多出的目標不再對應任何碼位
+                            if let Some(code) = codes.next() {
+                                cmap.unicode.insert(code, utf16_be(bytes));
+                            }
+                        }
+                        i += 1;
+                    }
+                    operands.clear();
+                }
+                token => {
+                    operands.push(token);
+                    match (section.as_str(), &operands[..]) {
+                        ("begincodespacerange", &[CMapToken::Hex(lo), CMapToken::Hex(hi)]) => {
+                            cmap.codespace.push((lo.len(), be_code(lo), be_code(hi)));
+                            operands.clear();
+                        }
+                        ("beginbfchar", &[CMapToken::Hex(code), CMapToken::Hex(dst)]) => {
+                            cmap.unicode.insert(be_code(code), utf16_be(dst));
+                            operands.clear();
+                        }
+                        (
+                            "beginbfrange",
+                            &[CMapToken::Hex(lo), CMapToken::Hex(hi), CMapToken::Hex(dst)],
+                        ) => {
+                            let mut dst = dst.clone();
+                            for code in cmap.code_range(lo, hi).into_iter().flatten() {
+                                cmap.unicode.insert(code, utf16_be(&dst));
+                                if let Some(last) = dst.last_mut() {
+                                    *last = last.wrapping_add(1);
+                                }
+                            }
+                            operands.clear();
+                        }
+                        ("begincidchar", &[CMapToken::Hex(code), CMapToken::Int(cid)]) => {
+                            cmap.cid_ranges.push((be_code(code), be_code(code), *cid));
+                            operands.clear();
+                        }
+                        (
+                            "begincidrange",
+                            &[CMapToken::Hex(lo), CMapToken::Hex(hi), CMapToken::Int(cid)],
+                        ) => {
+                            cmap.cid_ranges.push((be_code(lo), be_code(hi), *cid));
+                            operands.clear();
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            i += 1;
+        }
+
+        cmap
+    }
+
+    // bfrange 的碼位範圍；兩端字節數不同、超出已聲明的碼位空間或過大時返回 None
+    fn code_range(&self, lo: &[u8], hi: &[u8]) -> Option<RangeInclusive<u32>> {
+        if lo.len() != hi.len() || lo.is_empty() || lo.len() > 4 {
+            return None;
+        }
+        let (lo_code, hi_code) = (be_code(lo), be_code(hi));
+        if hi_code < lo_code || hi_code - lo_code >= MAX_RANGE_SPAN {
+            return None;
+        }
+        let declared = self.codespace.is_empty()
+            || self
+                .codespace
+                .iter()
+                .any(|&(len, start, end)| len == lo.len() && start <= lo_code && hi_code <= end);
+
+        declared.then_some(lo_code..=hi_code)
+    }
+
+    fn cid(&self, code: u32) -> Option<u32> {
+        self.cid_ranges
+            .iter()
+            .find(|&&(lo, hi, _)| code >= lo && code <= hi)
+            .map(|&(lo, _, cid)| cid + code - lo)
+    }
+}
+
+fn utf16_be(bytes: &[u8]) -> String {
+    let units = bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+fn tokenize_cmap(data: &[u8]) -> Vec<CMapToken> {
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < data.len() {
+        let b = data[i];
+        match b {
+            b'%' => {
+                while i < data.len() && data[i] != b'\n' && data[i] != b'\r' {
+                    i += 1;
+                }
+            }
+            b'<' if data.get(i + 1) == Some(&b'<') => i += 2,
+            b'>' if data.get(i + 1) == Some(&b'>') => i += 2,
+            b'<' => {
+                let start = i + 1;
+                while i < data.len() && data[i] != b'>' {
+                    i += 1;
+                }
+                let digits = data[start..i.min(data.len())]
+                    .iter()
+                    .filter(|b| b.is_ascii_hexdigit())
+                    .copied()
+                    .collect::<Vec<_>>();
+                let bytes = digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let hex = std::str::from_utf8(pair).unwrap_or("0");
+                        let value = u8::from_str_radix(hex, 16).unwrap_or(0);
+                        if pair.len() == 1 {
+                            value << 4
+                        } else {
+                            value
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                tokens.push(CMapToken::Hex(bytes));
+                i += 1;
+            }
+            b'[' => {
+                tokens.push(CMapToken::ArrayStart);
+                i += 1;
+            }
+            b']' => {
+                tokens.push(CMapToken::ArrayEnd);
+                i += 1;
+            }
+            b'(' => {
+                let mut depth = 1;
+                i += 1;
+                while i < data.len() && depth > 0 {
+                    match data[i] {
+                        b'\\' => i += 1,
+                        b'(' => depth += 1,
+                        b')' => depth -= 1,
+                        _ => {}
+                    }
+                    i += 1;
+                }
+            }
+            _ if b.is_ascii_whitespace() => i += 1,
+            _ => {
+                let start = i;
+                while i < data.len()
+                    && !data[i].is_ascii_whitespace()
+                    && !b"<>[]()/%".contains(&data[i])
+                {
+                    i += 1;
+                }
+                if i == start {
+                    // 名稱對象
+                    i += 1;
+                    while i < data.len()
+                        && !data[i].is_ascii_whitespace()
+                        && !b"<>[]()/%".contains(&data[i])
+                    {
+                        i += 1;
+                    }
+                    continue;
+                }
+                let word = String::from_utf8_lossy(&data[start..i]).to_string();
+                match word.parse::<u32>() {
+                    Ok(num) => tokens.push(CMapToken::Int(num)),
+                    Err(_) => tokens.push(CMapToken::Keyword(word)),
+                }
+            }
+        }
+    }
+
+    tokens
+}
+
+#[cfg(test)]
+mod test {
+    use lopdf::{dictionary, Stream};
+
+    use super::*;
+    use crate::{
+        font::{build_font, FontGlyph},
+        outline::Outline,
+        parse::{parse_details, parse_font_drawn},
+    };
+
+    // 以 5 字節整數寫入 CFF DICT 的操作數，使偏移量的長度固定
+    fn cff_int(value: usize) -> Vec<u8> {
+        let mut data = vec![29];
+        data.extend((value as i32).to_be_bytes());
+        data
+    }
+
+    fn cff_index(items: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = (items.len() as u16).to_be_bytes().to_vec();
+        if items.is_empty() {
+            return data;
+        }
+        data.push(2);
+        let mut offset = 1u16;
+        data.extend(offset.to_be_bytes());
+        for item in items {
+            offset += item.len() as u16;
+            data.extend(offset.to_be_bytes());
+        }
+        for item in items {
+            data.extend(item);
+        }
+        data
+    }
+
+    // 左下角在 (x, x)、邊長 size 的正方形（千分之一 em）
+    fn square_charstring(x: i16, size: i16) -> Vec<u8> {
+        let mut data = vec![];
+        let mut push = |values: &[i16], operator: u8| {
+            for value in values {
+                data.push(28);
+                data.extend(value.to_be_bytes());
+            }
+            data.push(operator);
+        };
+        // rmoveto、rlineto
+        push(&[x, x], 21);
+        push(&[size, 0, 0, size, -size, 0], 5);
+        data.push(14);
+        data
+    }
+
+    // 裸 CFF 字體；給出 `cids` 時爲 CID 字體，第 n 個字形的 CID 爲 cids[n]
+    fn build_cff(glyphs: &[Vec<u8>], cids: Option<&[u16]>) -> Vec<u8> {
+        let top_dict = |[charset, fd_array, fd_select, char_strings]: [usize; 4]| {
+            let mut data = vec![];
+            if cids.is_some() {
+                for _ in 0..3 {
+                    data.extend(cff_int(0));
+                }
+                data.extend([12, 30]);
+            }
+            data.extend(cff_int(charset));
+            data.push(15);
+            if cids.is_some() {
+                data.extend(cff_int(fd_array));
+                data.extend([12, 36]);
+                data.extend(cff_int(fd_select));
+                data.extend([12, 37]);
+            }
+            data.extend(cff_int(char_strings));
+            data.push(17);
+            data
+        };
+
+        let head = [
+            vec![1, 0, 4, 1],
+            cff_index(&[b"Test".to_vec()]),
+            cff_index(&[top_dict([0; 4])]),
+            cff_index(&[]),
+            cff_index(&[]),
+        ]
+        .concat();
+        let mut offsets = [0; 4];
+        let mut tail = vec![];
+        // 簡單字體的字符集取 SID 1、2……，即標準編碼的 space、exclam……
+        offsets[0] = head.len();
+        tail.push(0);
+        for sid in 1..glyphs.len() as u16 {
+            tail.extend(cids.map_or(sid, |cids| cids[sid as usize]).to_be_bytes());
+        }
+        if let Some(cids) = cids {
+            offsets[1] = head.len() + tail.len();
+            tail.extend(cff_index(&[vec![]]));
+            offsets[2] = head.len() + tail.len();
+            tail.push(0);
+            tail.extend(vec![0; cids.len()]);
+        }
+        offsets[3] = head.len() + tail.len();
+        tail.extend(cff_index(glyphs));
+
+        [
+            vec![1, 0, 4, 1],
+            cff_index(&[b"Test".to_vec()]),
+            cff_index(&[top_dict(offsets)]),
+            cff_index(&[]),
+            cff_index(&[]),
+            tail,
+        ]
+        .concat()
+    }
+
+    fn square(x: f32, size: f32) -> Outline {
+        let (left, right) = (x, x + size);
+        Outline::parse(&format!("M{left} {left}H{right}V{right}H{left}Z")).unwrap()
+    }
+
+    fn stream(doc: &mut Document, dict: Dictionary, data: Vec<u8>) -> ObjectId {
+        doc.add_object(Stream::new(dict, data))
+    }
+
+    // 一頁包含四種字體的文件：
+    // F1 爲 CIDFontType2，CIDToGIDMap 交換兩個字形；F2 爲 Type3，文字取自 ActualText；
+    // F3 爲簡單的 Type1C；F4 爲 CIDFontType0C，字形號須經字符集查得
+    fn fixture_pdf() -> Vec<u8> {
+        let mut doc = Document::with_version("1.7");
+
+        let truetype = build_font(
+            "Test",
+            &[
+                FontGlyph {
+                    name: "small".to_string(),
+                    codepoint: None,
+                    outline: square(0.2, 0.2),
+                },
+                FontGlyph {
+                    name: "big".to_string(),
+                    codepoint: None,
+                    outline: square(0.1, 0.8),
+                },
+            ],
+            0.0,
+        )
+        .unwrap();
+        let font_file = stream(&mut doc, dictionary! {}, truetype);
+        let cid_to_gid = stream(&mut doc, dictionary! {}, vec![0, 0, 0, 2, 0, 1]);
+        let descriptor = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "Test",
+            "FontFile2" => font_file,
+        });
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "Test",
+            "FontDescriptor" => descriptor,
+            "CIDToGIDMap" => cid_to_gid,
+        });
+        let to_unicode = stream(
+            &mut doc,
+            dictionary! {},
+            b"1 begincodespacerange <0000> <FFFF> endcodespacerange
+1 beginbfrange <0001> <0002> [<4E00> <4E8C>] endbfrange"
+                .to_vec(),
+        );
+        let f1 = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "Test",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+            "ToUnicode" => to_unicode,
+        });
+
+        let proc_a = stream(
+            &mut doc,
+            dictionary! {},
+            b"1000 0 0 0 1000 1000 d1 100 100 800 800 re f".to_vec(),
+        );
+        let f2 = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type3",
+            "FontBBox" => vec![0.into(), 0.into(), 1000.into(), 1000.into()],
+            "FontMatrix" => vec![0.001.into(), 0.into(), 0.into(), 0.001.into(), 0.into(), 0.into()],
+            "CharProcs" => dictionary! { "a" => proc_a },
+            "Encoding" => dictionary! { "Differences" => vec![65.into(), "a".into()] },
+            "FirstChar" => 65,
+            "LastChar" => 65,
+            "Widths" => vec![1000.into()],
+        });
+
+        let notdef = vec![14];
+        let type1c = build_cff(
+            &[notdef.clone(), notdef.clone(), square_charstring(100, 800)],
+            None,
+        );
+        let font_file = stream(&mut doc, dictionary! { "Subtype" => "Type1C" }, type1c);
+        let descriptor = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "Simple",
+            "FontFile3" => font_file,
+        });
+        let f3 = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Simple",
+            "FontDescriptor" => descriptor,
+            "Encoding" => "WinAnsiEncoding",
+            "FirstChar" => 33,
+            "LastChar" => 33,
+            "Widths" => vec![1000.into()],
+        });
+
+        let cid_cff = build_cff(
+            &[
+                notdef,
+                square_charstring(200, 200),
+                square_charstring(100, 800),
+            ],
+            Some(&[0, 5, 9]),
+        );
+        let font_file = stream(
+            &mut doc,
+            dictionary! { "Subtype" => "CIDFontType0C" },
+            cid_cff,
+        );
+        let descriptor = doc.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "Cid",
+            "FontFile3" => font_file,
+        });
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType0",
+            "BaseFont" => "Cid",
+            "FontDescriptor" => descriptor,
+        });
+        let to_unicode = stream(
+            &mut doc,
+            dictionary! {},
+            b"1 begincodespacerange <0000> <FFFF> endcodespacerange
+2 beginbfchar <0005> <4E09> <0009> <4E03> endbfchar"
+                .to_vec(),
+        );
+        let f4 = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "Cid",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+            "ToUnicode" => to_unicode,
+        });
+
+        let content = stream(
+            &mut doc,
+            dictionary! {},
+            b"BT /F1 10 Tf 1 0 0 1 100 700 Tm <00010002> Tj ET
+BT /F2 10 Tf 1 0 0 1 200 700 Tm /Span <</ActualText <FEFF4E01>>> BDC (A) Tj EMC ET
+BT /F3 10 Tf 1 0 0 1 300 700 Tm (!) Tj ET
+BT /F4 10 Tf 1 0 0 1 400 700 Tm <00090005> Tj ET"
+                .to_vec(),
+        );
+        let pages_id = doc.new_object_id();
+        let page = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "MediaBox" => vec![0.into(), 0.into(), 600.into(), 800.into()],
+            "Contents" => content,
+            "Resources" => dictionary! {
+                "Font" => dictionary! { "F1" => f1, "F2" => f2, "F3" => f3, "F4" => f4 },
+            },
+        });
+        doc.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page.into()],
+                "Count" => 1,
+            }
+            .into(),
+        );
+        let catalog = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog);
+
+        let mut data = vec![];
+        doc.save_to(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn native_page_svg() {
+        let pdf = PdfDocument::from_bytes(&fixture_pdf()).unwrap();
+        let page_svg = pdf.page_svg(0).unwrap();
+        let details = parse_details(&page_svg).unwrap();
+        let font_dic = parse_font_drawn(&page_svg).unwrap();
+
+        // (文字, 左下角的頁面坐標, 字形在 em 中的範圍)
+        let drawn = details
+            .iter()
+            .map(|(text, font_name, matrix)| {
+                let bounds = Outline::parse(&font_dic[font_name]).unwrap().bounds();
+                let bounds = bounds.map(|bounds| bounds.map(|v| (v * 1e4).round() / 1e4));
+                (text.as_str(), (matrix[4], matrix[5]), bounds)
+            })
+            .collect::<Vec<_>>();
+        let big = Some([0.1, 0.1, 0.9, 0.9]);
+        let small = Some([0.2, 0.2, 0.4, 0.4]);
+        assert_eq!(
+            drawn,
+            [
+                ("一", (100.0, 100.0), big),
+                ("二", (110.0, 100.0), small),
+                ("丁", (200.0, 100.0), big),
+                ("!", (300.0, 100.0), big),
+                ("七", (400.0, 100.0), big),
+                ("三", (410.0, 100.0), small),
+            ]
+        );
+        assert!(details
+            .iter()
+            .all(|(_, _, matrix)| matrix[..4] == [10.0, 0.0, 0.0, -10.0]));
+    }
+
+    #[test]
+    fn bounded_bfrange() {
+        let cmap = CMap::parse(
+            b"1 begincodespacerange <0000> <FFFF> endcodespacerange
+3 beginbfrange
+<0000> <FFFFFFFF> <4E00>
+<0010> <0011> [<4E01> <4E02> <4E03>]
+<FFFE> <FFFF> [<4E04> <4E05> <4E06>]
+endbfrange
+2 beginbfrange
+<00000020> <00000021> <0041>
+<0030> <0031> <0030>
+endbfrange",
+        );
+
+        assert_eq!(cmap.unicode.len(), 6);
+        assert_eq!(cmap.unicode.get(&0x11).map(String::as_str), Some("丂"));
+        assert_eq!(cmap.unicode.get(&0xFFFF).map(String::as_str), Some("丅"));
+        assert_eq!(cmap.unicode.get(&0x31).map(String::as_str), Some("1"));
+        assert_eq!(cmap.unicode.get(&0x20), None);
+    }
+
+    // 需要安裝 PyMuPDF；`CJK_CHART_PDF` 指向圖表文件時比較其第一頁圖表，否則比較上面的測試文件
+    #[cfg(feature = "pymupdf")]
+    #[test]
+    fn matches_pymupdf() {
+        use crate::pymupdf::PyMuPdfDocument;
+
+        let (path, page_idx) = match std::env::var("CJK_CHART_PDF") {
+            Ok(path) => (std::path::PathBuf::from(path), 1),
+            Err(_) => {
+                let path = crate::test_dir("pymupdf").join("fixture.pdf");
+                std::fs::write(&path, fixture_pdf()).unwrap();
+                (path, 0)
+            }
+        };
+        let native = PdfDocument::open(&path)
+            .unwrap()
+            .page_svg(page_idx)
+            .unwrap();
+        let pymupdf = PyMuPdfDocument::open(path.to_str().unwrap())
+            .expect("無法經由 PyMuPDF 打開文件，請先安裝 PyMuPDF")
+            .page_svg(page_idx)
+            .unwrap();
+
+        // 字形鍵因後端而異，故比較文字、位置與字形的範圍
+        let drawn = |page_svg: &str| {
+            let font_dic = parse_font_drawn(page_svg).unwrap();
+            parse_details(page_svg)
+                .unwrap()
+                .into_iter()
+                .map(|(text, font_name, matrix)| {
+                    let bounds = Outline::parse(&font_dic[&font_name]).unwrap().bounds();
+                    (text, matrix, bounds)
+                })
+                .collect::<Vec<_>>()
+        };
+        let (native, pymupdf) = (drawn(&native), drawn(&pymupdf));
+        assert_eq!(native.len(), pymupdf.len());
+        let close = |a: f64, b: f64| (a - b).abs() < 0.01;
+        for ((text, matrix, bounds), (py_text, py_matrix, py_bounds)) in native.iter().zip(&pymupdf)
+        {
+            assert_eq!(text, py_text);
+            assert!(matrix.iter().zip(py_matrix).all(|(&a, &b)| close(a, b)));
+            match (bounds, py_bounds) {
+                (Some(a), Some(b)) => {
+                    assert!(a.iter().zip(b).all(|(&a, &b)| close(a as f64, b as f64)))
+                }
+                (a, b) => assert_eq!(a, b),
+            }
+        }
+    }
+}
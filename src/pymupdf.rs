@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use pyo3::{types::IntoPyDict, Py, PyAny, PyResult, Python};
+
+use crate::{
+    error::Result,
+    page_source::{DocumentMetadata, PageSource},
+};
+
+/// 經由 PyMuPDF 的 `get_svg_image` 取得頁面 SVG
+pub struct PyMuPdfDocument {
+    doc: Py<PyAny>,
+    page_count: usize,
+}
+
+impl PyMuPdfDocument {
+    pub fn open(input: &str) -> Result<Self> {
+        let doc = Python::with_gil(|py| -> PyResult<Self> {
+            let fitz = py.import("fitz")?;
+            let doc = fitz.call_method1("open", (input,))?;
+            let page_count: usize = doc.call_method0("__len__")?.extract()?;
+
+            Ok(Self {
+                doc: doc.into(),
+                page_count,
+            })
+        })?;
+
+        Ok(doc)
+    }
+}
+
+impl PageSource for PyMuPdfDocument {
+    fn page_count(&self) -> usize {
+        self.page_count
+    }
+
+    fn page_svg(&self, page_idx: usize) -> Result<String> {
+        let page_svg = Python::with_gil(|py| -> PyResult<String> {
+            let fitz_matrix_identity = (1.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+            let page = self
+                .doc
+                .as_ref(py)
+                .call_method1("__getitem__", (page_idx,))?;
+            page.call_method(
+                "get_svg_image",
+                (),
+                Some(vec![("matrix", &fitz_matrix_identity)].into_py_dict(py)),
+            )?
+            .extract()
+        })?;
+
+        Ok(page_svg)
+    }
+
+    fn metadata(&self) -> DocumentMetadata {
+        let metadata: HashMap<String, Option<String>> = Python::with_gil(|py| {
+            self.doc
+                .as_ref(py)
+                .getattr("metadata")
+                .and_then(|obj| obj.extract())
+        })
+        .unwrap_or_default();
+        let field = |key: &str| {
+            metadata
+                .get(key)
+                .cloned()
+                .flatten()
+                .filter(|text| !text.is_empty())
+        };
+
+        DocumentMetadata {
+            title: field("title"),
+            author: field("author"),
+            subject: field("subject"),
+            creator: field("creator"),
+            producer: field("producer"),
+            creation_date: field("creationDate"),
+            modification_date: field("modDate"),
+        }
+    }
+}
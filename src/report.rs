@@ -0,0 +1,137 @@
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::error::{Error, Result};
+
+/// 寬鬆模式下跳過或部分輸出的一處問題，或經過處理才輸出的字形
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Problem {
+    pub page: Option<usize>,
+    #[serde(serialize_with = "serialize_hex_list")]
+    pub codepoints: Vec<u32>,
+    pub reason: String,
+}
+
+fn serialize_hex_list<S: serde::Serializer>(
+    codepoints: &[u32],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(
+        codepoints
+            .iter()
+            .map(|codepoint| format!("{:X}", codepoint)),
+    )
+}
+
+/// 一次轉換中遇到的所有問題
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Report {
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn push(&mut self, page: Option<usize>, codepoints: Vec<u32>, err: &Error) {
+        let reason = match err {
+            Error::Page { source, .. } => source.to_string(),
+            err => err.to_string(),
+        };
+        eprintln!("已跳過: {}", err);
+
+        self.problems.push(Problem {
+            page: page.or_else(|| err.page()),
+            codepoints,
+            reason,
+        });
+    }
+
+    /// 記錄已輸出但經過處理的字形，不影響轉換結果
+    pub fn warn(&mut self, page: Option<usize>, codepoints: Vec<u32>, reason: String) {
+        eprintln!("警告: {}", reason);
+
+        self.problems.push(Problem {
+            page,
+            codepoints,
+            reason,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.problems.len()
+    }
+
+    /// 報告的路徑，如 `basic.xlsx` 對應 `basic.report.json` 與 `basic.report.txt`
+    pub fn paths<P: AsRef<Path>>(output: P) -> (PathBuf, PathBuf) {
+        let output = output.as_ref();
+        (
+            output.with_extension("report.json"),
+            output.with_extension("report.txt"),
+        )
+    }
+
+    /// 以 JSON 與文本兩種格式保存在輸出文件旁邊
+    pub fn save<P: AsRef<Path>>(&self, output: P) -> Result<()> {
+        let (json_path, text_path) = Self::paths(output);
+
+        let file = File::create(&json_path).map_err(|err| Error::file(&json_path, err))?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, self)?;
+        writer.flush()?;
+
+        fs::write(&text_path, self.to_text()).map_err(|err| Error::file(&text_path, err))?;
+
+        Ok(())
+    }
+
+    /// 每個問題一行：頁碼、碼位、原因
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for problem in &self.problems {
+            let page = match problem.page {
+                Some(page) => format!("{:03}", page),
+                None => "-".to_string(),
+            };
+            let codepoints = match problem.codepoints.as_slice() {
+                [] => "-".to_string(),
+                [codepoint] => format!("{:X}", codepoint),
+                [first, .., last] => {
+                    format!("{:X}-{:X} ({})", first, last, problem.codepoints.len())
+                }
+            };
+            writeln!(text, "{}\t{}\t{}", page, codepoints, problem.reason).unwrap();
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn report_text() {
+        let mut report = Report::default();
+        report.push(
+            None,
+            vec![0x4E00, 0x4E01, 0x4E02],
+            &Error::MissingElement("g").at_page(3),
+        );
+        report.push(Some(5), vec![0x4E10], &Error::Render("空白".to_string()));
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(
+            report.to_text(),
+            "003\t4E00-4E02 (3)\t頁面 SVG 中缺少 <g> 元素\n005\t4E10\t渲染字形失敗: 空白\n"
+        );
+    }
+}
@@ -0,0 +1,569 @@
+use std::{fmt, str::FromStr};
+
+use serde::Serialize;
+
+/// IRG 字源所屬的地區，次序即 xlsx 中各地區列的次序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Region {
+    /// 中國大陸
+    G,
+    /// 香港
+    H,
+    /// 澳門
+    M,
+    /// 臺灣
+    T,
+    /// 日本
+    J,
+    /// 韓國
+    K,
+    /// 朝鮮
+    KP,
+    /// 越南
+    V,
+    /// SAT 大藏經文本數據庫
+    S,
+    /// Unicode 聯盟的其他字源，如 `UCI-00001`
+    U,
+    /// 英國
+    UK,
+    /// Unicode 技術委員會，如 `UTC-00001`
+    UTC,
+}
+
+impl Region {
+    pub const ALL: [Region; 12] = [
+        Region::G,
+        Region::H,
+        Region::M,
+        Region::T,
+        Region::J,
+        Region::K,
+        Region::KP,
+        Region::V,
+        Region::S,
+        Region::U,
+        Region::UK,
+        Region::UTC,
+    ];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Region::G => "G",
+            Region::H => "H",
+            Region::M => "M",
+            Region::T => "T",
+            Region::J => "J",
+            Region::K => "K",
+            Region::KP => "KP",
+            Region::V => "V",
+            Region::S => "S",
+            Region::U => "U",
+            Region::UK => "UK",
+            Region::UTC => "UTC",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Region::G => "中國大陸",
+            Region::H => "香港",
+            Region::M => "澳門",
+            Region::T => "臺灣",
+            Region::J => "日本",
+            Region::K => "韓國",
+            Region::KP => "朝鮮",
+            Region::V => "越南",
+            Region::S => "SAT",
+            Region::U => "Unicode",
+            Region::UK => "英國",
+            Region::UTC => "UTC",
+        }
+    }
+
+    /// 由字源集的名稱得出地區，如 `GHZ`、`KP1`、`UTC`
+    pub fn of_set(set: &str) -> Option<Region> {
+        for region in [Region::UTC, Region::UK, Region::KP] {
+            if set.starts_with(region.code()) {
+                return Some(region);
+            }
+        }
+
+        Region::ALL
+            .into_iter()
+            .find(|region| region.code().len() == 1 && set.starts_with(region.code()))
+    }
+}
+
+impl fmt::Display for Region {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Serialize for Region {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+/// 保留前導零的十進制數，如 `00123`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Number {
+    pub value: u32,
+    pub width: usize,
+}
+
+impl FromStr for Number {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(());
+        }
+
+        Ok(Number {
+            value: s.parse().map_err(|_| ())?,
+            width: s.len(),
+        })
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:0width$}", self.value, width = self.width)
+    }
+}
+
+/// 字源在字源集中的位置
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SourcePosition {
+    /// 字符集中的編碼，如 `G0-523B` 的 `523B`
+    Code(u16),
+    /// 流水號，如 `UTC-00001` 的 `00001`
+    Serial(Number),
+    /// 字典中的頁碼與位置，如 `GKX-0078.01`，或頁、行、列，如 `GXX-0012.03.4`
+    Page {
+        page: Number,
+        line: Option<Number>,
+        column: Number,
+    },
+    /// 其他形式的位置，原樣保留，如 `JH-JT4D4F` 的 `JT4D4F`
+    Other(String),
+}
+
+impl fmt::Display for SourcePosition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourcePosition::Code(code) => write!(f, "{:04X}", code),
+            SourcePosition::Serial(number) => write!(f, "{}", number),
+            SourcePosition::Page {
+                page,
+                line: Some(line),
+                column,
+            } => write!(f, "{}.{}.{}", page, line, column),
+            SourcePosition::Page {
+                page,
+                line: None,
+                column,
+            } => write!(f, "{}.{}", page, column),
+            SourcePosition::Other(text) => f.write_str(text),
+        }
+    }
+}
+
+/// IRG 字源，如 `G0-523B`、`T1-4421`、`GKX-0078.01`、`UTC-00001`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourceRef {
+    pub region: Region,
+    /// 字源集，含地區前綴，如 `G0`、`GHZ`、`KP1`
+    pub set: String,
+    pub position: SourcePosition,
+}
+
+impl FromStr for SourceRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = |reason: &str| format!("無效的字源 {}: {}", s, reason);
+        let (set, position) = s.trim().split_once('-').ok_or_else(|| err("缺少連字符"))?;
+        if set.is_empty()
+            || !set
+                .bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+        {
+            return Err(err("字源集名稱須爲大寫字母或數字"));
+        }
+        let region = Region::of_set(set).ok_or_else(|| err("未知的地區"))?;
+        let position = parse_position(region, position).ok_or_else(|| err("無效的位置"))?;
+
+        Ok(SourceRef {
+            region,
+            set: set.to_string(),
+            position,
+        })
+    }
+}
+
+impl fmt::Display for SourceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.set, self.position)
+    }
+}
+
+impl Serialize for SourceRef {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// 國家標準字符集中的位置，如 GB 2312 的區位、CNS 11643 的字面、行、列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct StandardCode {
+    pub standard: &'static str,
+    /// 多字面標準的字面號
+    pub plane: Option<u8>,
+    pub row: u8,
+    pub cell: u8,
+    /// ISO 2022 形式的編碼，即 `(row + 0x20) << 8 | (cell + 0x20)`
+    #[serde(serialize_with = "serialize_code")]
+    pub code: u16,
+}
+
+fn serialize_code<S: serde::Serializer>(code: &u16, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{:04X}", code))
+}
+
+impl StandardCode {
+    /// 字源集對應的標準及字面，如 `G0` → GB 2312、`T3` → CNS 11643 第 3 字面
+    pub fn standard_of(set: &str) -> Option<(&'static str, Option<u8>)> {
+        let standard = match set {
+            "G0" => ("GB 2312", None),
+            "G1" => ("GB 12345", None),
+            "G3" => ("GB 7589", None),
+            "G5" => ("GB 7590", None),
+            "G7" => ("GB 8565.2", None),
+            "GE" => ("GB 16500", None),
+            "GH" => ("GB/T 15564", None),
+            "J0" => ("JIS X 0208", None),
+            "J1" => ("JIS X 0212", None),
+            "J3" | "J3A" | "J13" | "J13A" => ("JIS X 0213", Some(1)),
+            "J4" | "J14" => ("JIS X 0213", Some(2)),
+            "K0" => ("KS X 1001", None),
+            "K1" => ("KS X 1002", None),
+            "KP0" => ("KPS 9566", None),
+            "KP1" => ("KPS 10721", None),
+            "V0" => ("TCVN 5773", None),
+            "V1" => ("TCVN 6056", None),
+            "V2" => ("VHN 01", None),
+            "V3" => ("VHN 02", None),
+            _ => {
+                // T1–T7 爲 CNS 11643 第 1–7 字面，TA–TF 爲第 10–15 字面
+                let plane = set.strip_prefix('T')?;
+                let plane = match plane.as_bytes() {
+                    [b @ b'1'..=b'7'] => b - b'0',
+                    [b @ b'A'..=b'F'] => b - b'A' + 10,
+                    _ => return None,
+                };
+                ("CNS 11643", Some(plane))
+            }
+        };
+
+        Some(standard)
+    }
+}
+
+impl fmt::Display for StandardCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.standard)?;
+        if let Some(plane) = self.plane {
+            write!(f, "{}-", plane)?;
+        }
+        write!(f, "{:02}-{:02}", self.row, self.cell)
+    }
+}
+
+impl SourceRef {
+    /// 直接引用國家標準的字源在該標準中的位置，其他字源爲 `None`
+    pub fn standard_code(&self) -> Option<StandardCode> {
+        let SourcePosition::Code(code) = self.position else {
+            return None;
+        };
+        let (standard, plane) = StandardCode::standard_of(&self.set)?;
+        // EUC 形式的編碼，如 `KP0-F9A1`，換算爲 ISO 2022 形式，區位均須在 1–94 之間
+        let code = code & 0x7F7F;
+        let [high, low] = code.to_be_bytes();
+        let position = |byte: u8| byte.checked_sub(0x20).filter(|idx| (1..=94).contains(idx));
+
+        Some(StandardCode {
+            standard,
+            plane,
+            row: position(high)?,
+            cell: position(low)?,
+            code,
+        })
+    }
+
+    /// 引用字典的字源在該字典中的卷、頁與字序，其他字源爲 `None`
+    pub fn dictionary_ref(&self) -> Option<DictionaryRef> {
+        let SourcePosition::Page {
+            page,
+            line: None,
+            column,
+        } = self.position
+        else {
+            return None;
+        };
+        let dictionary = Dictionary::of_set(&self.set)?;
+        // 五位頁碼的首位爲卷號，如 `GHZ-74603.02` 爲第 7 卷第 4603 頁
+        let (volume, page) = match page.width {
+            5 if dictionary.has_volumes() => (Some(page.value / 10000), page.value % 10000),
+            _ => (None, page.value),
+        };
+
+        Some(DictionaryRef {
+            dictionary,
+            volume,
+            page,
+            position: column.value,
+        })
+    }
+}
+
+/// 字源所引用的字典，次序即按字典排序時的次序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dictionary {
+    /// 康熙字典，`GKX`
+    KangxiZidian,
+    /// 漢語大字典，`GHZ`
+    HanyuDaZidian,
+    /// 漢語大字典第二版，`GHZR`
+    HanyuDaZidian2,
+    /// 中華大字典，`GDZ`
+    ZhonghuaDaZidian,
+    /// 中華字海，`GZH`
+    ZhonghuaZihai,
+}
+
+impl Dictionary {
+    pub fn of_set(set: &str) -> Option<Self> {
+        match set {
+            "GKX" => Some(Dictionary::KangxiZidian),
+            "GHZ" => Some(Dictionary::HanyuDaZidian),
+            "GHZR" => Some(Dictionary::HanyuDaZidian2),
+            "GDZ" => Some(Dictionary::ZhonghuaDaZidian),
+            "GZH" => Some(Dictionary::ZhonghuaZihai),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dictionary::KangxiZidian => "康熙字典",
+            Dictionary::HanyuDaZidian => "漢語大字典",
+            Dictionary::HanyuDaZidian2 => "漢語大字典（第二版）",
+            Dictionary::ZhonghuaDaZidian => "中華大字典",
+            Dictionary::ZhonghuaZihai => "中華字海",
+        }
+    }
+
+    /// 簡稱，用於 xlsx 中字形下方的註記
+    pub fn abbr(&self) -> &'static str {
+        match self {
+            Dictionary::KangxiZidian => "康熙",
+            Dictionary::HanyuDaZidian => "漢大",
+            Dictionary::HanyuDaZidian2 => "漢大二",
+            Dictionary::ZhonghuaDaZidian => "中大",
+            Dictionary::ZhonghuaZihai => "字海",
+        }
+    }
+
+    /// 分卷的字典，頁碼前附卷號
+    pub fn has_volumes(&self) -> bool {
+        matches!(self, Dictionary::HanyuDaZidian | Dictionary::HanyuDaZidian2)
+    }
+}
+
+impl Serialize for Dictionary {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+/// 字典中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct DictionaryRef {
+    pub dictionary: Dictionary,
+    pub volume: Option<u32>,
+    pub page: u32,
+    /// 該頁中的字序
+    pub position: u32,
+}
+
+impl fmt::Display for DictionaryRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ", self.dictionary.abbr())?;
+        if let Some(volume) = self.volume {
+            write!(f, "{}:", volume)?;
+        }
+        write!(f, "{}.{:02}", self.page, self.position)
+    }
+}
+
+// 四位十六進制且兩個字節均在 0x21–0x7E 或 0xA1–0xFE 之間 (香港字源不限) 的爲編碼，
+// 其餘全爲數字的爲流水號，以點分隔的爲頁碼與位置，其他大寫字母與數字的組合原樣保留
+fn parse_position(region: Region, text: &str) -> Option<SourcePosition> {
+    if text.contains('.') {
+        let parts = text
+            .split('.')
+            .map(|part| part.parse::<Number>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        return match parts[..] {
+            [page, column] => Some(SourcePosition::Page {
+                page,
+                line: None,
+                column,
+            }),
+            [page, line, column] => Some(SourcePosition::Page {
+                page,
+                line: Some(line),
+                column,
+            }),
+            _ => None,
+        };
+    }
+
+    let is_hex = text.len() == 4
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b));
+    if is_hex {
+        let code = u16::from_str_radix(text, 16).ok()?;
+        let [high, low] = code.to_be_bytes();
+        if region == Region::H
+            || [high, low].iter().all(|b| (0x21..=0x7E).contains(b))
+            || [high, low].iter().all(|b| (0xA1..=0xFE).contains(b))
+        {
+            return Some(SourcePosition::Code(code));
+        }
+    }
+
+    if let Ok(number) = text.parse() {
+        return Some(SourcePosition::Serial(number));
+    }
+    let is_label = !text.is_empty()
+        && text
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+    is_label.then(|| SourcePosition::Other(text.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_sources() {
+        let cases = [
+            ("G0-523B", Region::G, "G0"),
+            ("GHZ-10056.01", Region::G, "GHZ"),
+            ("GKX-0078.01", Region::G, "GKX"),
+            ("GZFY-00123", Region::G, "GZFY"),
+            ("T1-4421", Region::T, "T1"),
+            ("TF-2E41", Region::T, "TF"),
+            ("JMJ-012345", Region::J, "JMJ"),
+            ("KP1-3421", Region::KP, "KP1"),
+            ("K0-5443", Region::K, "K0"),
+            ("H-9BA9", Region::H, "H"),
+            ("MAC-00123", Region::M, "MAC"),
+            ("SAT-04081", Region::S, "SAT"),
+            ("UTC-00001", Region::UTC, "UTC"),
+            ("UCI-00001", Region::U, "UCI"),
+            ("UK-02846", Region::UK, "UK"),
+            ("V1-4A21", Region::V, "V1"),
+            ("JH-JT4D4F", Region::J, "JH"),
+            ("JH-IB0091", Region::J, "JH"),
+            ("KP0-F9A1", Region::KP, "KP0"),
+        ];
+        for (text, region, set) in cases {
+            let source: SourceRef = text.parse().unwrap();
+            assert_eq!((source.region, source.set.as_str()), (region, set));
+            assert_eq!(source.to_string(), text);
+        }
+
+        let source: SourceRef = "GKX-0078.01".parse().unwrap();
+        assert_eq!(
+            source.position,
+            SourcePosition::Page {
+                page: Number {
+                    value: 78,
+                    width: 4
+                },
+                line: None,
+                column: Number { value: 1, width: 2 },
+            }
+        );
+        // 超出 94×94 範圍的四位數字視爲流水號
+        let source: SourceRef = "GDM-0001".parse().unwrap();
+        assert!(matches!(source.position, SourcePosition::Serial(_)));
+        let source: SourceRef = "JH-JT4D4F".parse().unwrap();
+        assert_eq!(source.position, SourcePosition::Other("JT4D4F".to_string()));
+
+        for text in [
+            "T",
+            "X1-4421",
+            "g0-523B",
+            "G0-",
+            "G0-52 3B",
+            "GKX-1.2.3.4",
+            "JH-jt4d4f",
+        ] {
+            assert!(text.parse::<SourceRef>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn decode_standards() {
+        let decode = |text: &str| text.parse::<SourceRef>().unwrap().standard_code();
+
+        let code = decode("G0-523B").unwrap();
+        assert_eq!((code.standard, code.row, code.cell), ("GB 2312", 50, 27));
+        assert_eq!(code.to_string(), "GB 2312 50-27");
+        assert_eq!(decode("T3-2144").unwrap().to_string(), "CNS 11643 3-01-36");
+        assert_eq!(decode("TF-2E41").unwrap().plane, Some(15));
+        assert_eq!(decode("J0-3021").unwrap().to_string(), "JIS X 0208 16-01");
+        assert_eq!(decode("K0-4A21").unwrap().standard, "KS X 1001");
+        let code = decode("KP0-F9A1").unwrap();
+        assert_eq!((code.row, code.cell, code.code), (89, 1, 0x7921));
+        assert_eq!(decode("JH-JT4D4F"), None);
+        // 手工構造的字源可能不在區位範圍內
+        let out_of_range = |code| SourceRef {
+            region: Region::G,
+            set: "G0".to_string(),
+            position: SourcePosition::Code(code),
+        };
+        assert_eq!(out_of_range(0x0010).standard_code(), None);
+        assert_eq!(out_of_range(0x217F).standard_code(), None);
+        // 非國家標準或非編碼的字源
+        assert_eq!(decode("H-9BA9"), None);
+        assert_eq!(decode("GKX-0078.01"), None);
+        assert_eq!(decode("UTC-00001"), None);
+    }
+
+    #[test]
+    fn dictionary_refs() {
+        let lookup = |text: &str| text.parse::<SourceRef>().unwrap().dictionary_ref();
+
+        let hdz = lookup("GHZ-74603.02").unwrap();
+        assert_eq!(
+            (hdz.dictionary, hdz.volume, hdz.page, hdz.position),
+            (Dictionary::HanyuDaZidian, Some(7), 4603, 2)
+        );
+        assert_eq!(hdz.to_string(), "漢大 7:4603.02");
+        let kx = lookup("GKX-1234.05").unwrap();
+        assert_eq!((kx.volume, kx.page, kx.position), (None, 1234, 5));
+        // 康熙字典排在漢語大字典之前
+        assert!(kx < hdz);
+        assert_eq!(lookup("G0-523B"), None);
+    }
+}
@@ -1,6 +1,4 @@
-use duplicate::duplicate_item;
-
-use crate::parse::{ChartCode, ChartSource, ChartGraph};
+use std::ops::{Bound, RangeInclusive};
 
 #[macro_export]
 macro_rules! re {
@@ -15,99 +13,52 @@ macro_rules! re {
     }};
 }
 
-pub fn split_chart_source(raw_vec: &Vec<ChartSource>, threshold: f64) -> Vec<Vec<&ChartSource>> {
-    let mut it = raw_vec.iter().peekable();
-    let mut res = vec![];
-    let mut tmp = vec![];
-    loop {
-        if let Some(cs) = it.next() {
-            tmp.push(cs);
-            let y = cs.y;
-            let peek_y = match it.peek() {
-                Some(ele) => ele.y,
-                None => y,
-            };
-
-            if (peek_y - y).abs() > threshold {
-                res.push(tmp);
-                tmp = vec![];
-            }
-        } else {
-            res.push(tmp);
-            break;
-        }
-    }
-
-    res
-}
-
-pub fn split_chart_graph<'a>(
-    raw_vec: &'a Vec<ChartGraph<'a>>,
-    threshold: f64,
-) -> Vec<Vec<&'a ChartGraph<'a>>> {
-    let mut it = raw_vec.iter().peekable();
-    let mut res = vec![];
-    let mut tmp = vec![];
-    loop {
-        if let Some(cs) = it.next() {
-            tmp.push(cs);
-            let y = cs.y;
-            let peek_y = match it.peek() {
-                Some(ele) => ele.y,
-                None => y,
-            };
+/// 解析頁碼範圍，如 `5`、`1-10`、`3-`、`-20`
+pub fn parse_page_range(s: &str) -> Result<(Bound<usize>, Bound<usize>), String> {
+    let parse = |num: &str| {
+        num.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("無效的頁碼: {}", num))
+    };
 
-            if (peek_y - y).abs() > threshold {
-                res.push(tmp);
-                tmp = vec![];
-            }
-        } else {
-            res.push(tmp);
-            break;
-        }
-    }
+    let Some((start, end)) = s.split_once('-') else {
+        let page = parse(s)?;
+        return Ok((Bound::Included(page), Bound::Included(page)));
+    };
+    let start = match start.trim() {
+        "" => Bound::Unbounded,
+        num => Bound::Included(parse(num)?),
+    };
+    let end = match end.trim() {
+        "" => Bound::Unbounded,
+        num => Bound::Included(parse(num)?),
+    };
 
-    res
+    Ok((start, end))
 }
 
-pub trait EnhanceVec {
-    fn sort_y_x(&mut self, threshold: f64);
-    fn sort_x_y(&mut self, threshold: f64);
-}
+/// 解析十六進制的碼位範圍，如 `4E00-9FFF`、`U+20000..U+2A6DF`
+pub fn parse_codepoint_range(s: &str) -> Result<RangeInclusive<u32>, String> {
+    let parse = |num: &str| {
+        let num = num.trim();
+        let hex = num
+            .strip_prefix("U+")
+            .or_else(|| num.strip_prefix("u+"))
+            .or_else(|| num.strip_prefix("0x"))
+            .unwrap_or(num);
+        u32::from_str_radix(hex, 16).map_err(|_| format!("無效的碼位: {}", num))
+    };
 
-#[duplicate_item(
-    [
-        vec_type [Vec<ChartGraph<'_>>]
-        method_x [x]
-    ]
-    [
-        vec_type [Vec<ChartSource>]
-        method_x [x_min]
-    ]
-    [
-        vec_type [Vec<ChartCode>]
-        method_x [x_min]
-    ]
-)]
-
-impl EnhanceVec for vec_type {
-    fn sort_y_x(&mut self, threshold: f64) {
-        self.sort_by(|a, b| {
-            if (a.y - b.y).abs() < threshold {
-                a.method_x.partial_cmp(&b.method_x).unwrap()
-            } else {
-                a.y.partial_cmp(&b.y).unwrap()
-            }
-        });
+    let (start, end) = s
+        .split_once("..=")
+        .or_else(|| s.split_once(".."))
+        .or_else(|| s.split_once('-'))
+        .or_else(|| s.split_once('–'))
+        .unwrap_or((s, s));
+    let (start, end) = (parse(start)?, parse(end)?);
+    if start > end {
+        return Err(format!("碼位範圍的起點大於終點: {}", s));
     }
 
-    fn sort_x_y(&mut self, threshold: f64) {
-        self.sort_by(|a, b| {
-            if (a.method_x - b.method_x).abs() < threshold {
-                a.y.partial_cmp(&b.y).unwrap()
-            } else {
-                a.method_x.partial_cmp(&b.method_x).unwrap()
-            }
-        });
-    }
+    Ok(start..=end)
 }
@@ -1,8 +1,10 @@
 use std::{io::Cursor, path::Path};
 
-use image::{ImageFormat, Luma};
+use image::{ImageFormat, RgbImage};
 use once_cell::sync::Lazy;
-use rust_xlsxwriter::{Format, Image, Workbook};
+use rust_xlsxwriter::{DocProperties, Format, Image, Workbook};
+
+use crate::error::{Error, Result};
 
 pub struct Xlsx {
     workbook: Workbook,
@@ -10,6 +12,12 @@ pub struct Xlsx {
     current_sheet: usize,
 }
 
+impl Default for Xlsx {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Xlsx {
     pub fn new() -> Self {
         let mut workbook = Workbook::new();
@@ -21,96 +29,120 @@ impl Xlsx {
         }
     }
 
-    pub fn add_format(&mut self, current_row: u32, col_count: usize, col_max: usize) {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.current_sheet)
-            .unwrap();
-
-        for col in (col_count + 1)..=col_max {
-            worksheet
-                .write_with_format(current_row, col as u16, "", &MIDDLE_TOP_FORMAT)
-                .unwrap();
-            worksheet
-                .write_with_format(current_row + 1, col as u16, "", &MIDDLE_MIDDLE_FORMAT)
-                .unwrap();
-            worksheet
-                .write_with_format(current_row + 2, col as u16, "", &MIDDLE_BOTTOM_FORMAT)
-                .unwrap();
+    /// 字形所在的首列，其前爲碼位與字符兩列
+    pub const GLYPH_COL: usize = 2;
+
+    /// 以空格補齊第 `col_count + 1` 至 `col_max` 個字形列的邊框
+    pub fn add_format(&mut self, current_row: u32, col_count: usize, col_max: usize) -> Result<()> {
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+
+        for idx in (col_count + 1)..=col_max {
+            let col = (Self::GLYPH_COL + idx - 1) as u16;
+            let [top, middle, bottom] = cell_formats(idx == col_max);
+            worksheet.write_with_format(current_row, col, "", top)?;
+            worksheet.write_with_format(current_row + 1, col, "", middle)?;
+            worksheet.write_with_format(current_row + 2, col, "", bottom)?;
         }
 
-        if col_count != col_max {
-            worksheet
-                .write_with_format(current_row, col_max as u16, "", &LAST_TOP_FORMAT)
-                .unwrap();
+        Ok(())
+    }
+
+    /// 設置 `col_max` 個字形列的寬度以容納字形圖片
+    pub fn set_glyph_columns(&mut self, col_max: usize) -> Result<()> {
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+        for idx in 0..col_max {
+            worksheet.set_column_width_pixels((Self::GLYPH_COL + idx) as u16, 85)?;
         }
 
-        worksheet
-            .write_with_format(current_row + 1, col_max as u16, "", &LAST_MIDDLE_FORMAT)
-            .unwrap();
-        worksheet
-            .write_with_format(current_row + 2, col_max as u16, "", &LAST_BOTTOM_FORMAT)
-            .unwrap();
+        Ok(())
     }
 
+    /// 表頭：每列一個標題，並凍結表頭與碼位、字符兩列
+    pub fn add_header<S: AsRef<str>>(&mut self, titles: &[S]) -> Result<()> {
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+
+        for (col, title) in (0..).zip(titles) {
+            worksheet.write_with_format(self.current_row, col, title.as_ref(), &HEADER_FORMAT)?;
+        }
+        worksheet.set_freeze_panes(self.current_row + 1, Self::GLYPH_COL as u16)?;
+
+        self.current_row += 1;
+
+        Ok(())
+    }
+
+    /// 在表頭至當前行之間設置自動篩選，`col_max` 爲字形列數
+    pub fn add_autofilter(&mut self, col_max: usize) -> Result<()> {
+        if self.current_row < 2 {
+            return Ok(());
+        }
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+        let last_col = (Self::GLYPH_COL + col_max - 1) as u16;
+        worksheet.autofilter(0, 0, self.current_row - 1, last_col)?;
+
+        Ok(())
+    }
+
+    /// 每個字形佔一列：字源、圖片、註記各一行，`None` 爲帶邊框的空格
     pub fn add_row<S: AsRef<str>>(
         &mut self,
         codepoint_hex: &str,
-        images_with_sources: &Vec<(S, image::ImageBuffer<Luma<u8>, Vec<u8>>)>,
+        character: char,
+        images_with_sources: &[Option<(S, RgbImage, S)>],
         col_max: usize,
-    ) {
-        let worksheet = self
-            .workbook
-            .worksheet_from_index(self.current_sheet)
-            .unwrap();
-
-        worksheet
-            .set_row_height_pixels(self.current_row + 1, 85)
-            .unwrap();
-
-        worksheet
-            .merge_range(
-                self.current_row,
-                0,
-                self.current_row + 2,
-                0,
-                codepoint_hex,
-                &FIRST_FORMAT,
-            )
-            .unwrap();
-
-        for (col, (source, image_origin)) in (1..).zip(images_with_sources) {
-            if col == col_max as u16 {
-                worksheet
-                    .write_with_format(self.current_row, col, source.as_ref(), &LAST_TOP_FORMAT)
-                    .unwrap();
-            } else {
-                worksheet
-                    .write_with_format(self.current_row, col, source.as_ref(), &MIDDLE_TOP_FORMAT)
-                    .unwrap();
+    ) -> Result<()> {
+        // 字源多於 `col_max` 時右邊框隨之右移
+        let col_max = col_max.max(images_with_sources.len());
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+
+        worksheet.set_row_height_pixels(self.current_row + 1, 85)?;
+
+        worksheet.merge_range(
+            self.current_row,
+            0,
+            self.current_row + 2,
+            0,
+            codepoint_hex,
+            &FIRST_FORMAT,
+        )?;
+        worksheet.merge_range(
+            self.current_row,
+            1,
+            self.current_row + 2,
+            1,
+            &character.to_string(),
+            &CHARACTER_FORMAT,
+        )?;
+
+        for (idx, cell) in (1..).zip(images_with_sources) {
+            let col = (Self::GLYPH_COL + idx - 1) as u16;
+            let (source, note) = match cell {
+                Some((source, _, note)) => (source.as_ref(), note.as_ref()),
+                None => ("", ""),
+            };
+            let [top, middle, bottom] = cell_formats(idx == col_max);
+            worksheet.write_with_format(self.current_row, col, source, top)?;
+            worksheet.write_with_format(self.current_row + 1, col, "", middle)?;
+            worksheet.write_with_format(self.current_row + 2, col, note, bottom)?;
+
+            if let Some((_, image_origin, _)) = cell {
+                let mut buf = Cursor::new(vec![]);
+                image_origin
+                    .write_to(&mut buf, ImageFormat::Png)
+                    .map_err(|err| Error::Render(err.to_string()))?;
+                let mut image = Image::new_from_buffer(buf.get_ref())?;
+                // 不論圖片尺寸，均縮放至約 83 像素見方以放入格子
+                let scale = 83.2 / image_origin.width().max(1) as f64;
+                image.set_scale_width(scale).set_scale_height(scale);
+                worksheet.insert_image_with_offset(self.current_row + 1, col, &image, 1, 1)?;
             }
-            let mut buf = Cursor::new(vec![]);
-            image_origin.write_to(&mut buf, ImageFormat::Png).unwrap();
-            let mut image = Image::new_from_buffer(buf.get_ref()).unwrap();
-            image.set_scale_width(0.65).set_scale_height(0.65);
-
-            worksheet.set_column_width_pixels(col, 85).unwrap();
-            worksheet
-                .insert_image_with_offset(self.current_row + 1, col, &image, 1, 1)
-                .unwrap();
-
-            // 設置格式
-            worksheet
-                .write_with_format(self.current_row + 1, col, "", &MIDDLE_MIDDLE_FORMAT)
-                .unwrap();
-            worksheet
-                .write_with_format(self.current_row + 2, col, "", &MIDDLE_BOTTOM_FORMAT)
-                .unwrap();
         }
 
-        self.add_format(self.current_row, images_with_sources.len(), col_max);
+        self.add_format(self.current_row, images_with_sources.len(), col_max)?;
 
         self.current_row += 3;
+
+        Ok(())
     }
 
     pub fn next_sheet(&mut self) {
@@ -119,15 +151,69 @@ impl Xlsx {
         self.current_row = 0;
     }
 
-    pub fn save<P: AsRef<Path>>(&mut self, path: P) {
-        self.workbook.save(path).unwrap();
+    pub fn set_sheet_name(&mut self, name: &str) -> Result<()> {
+        self.workbook
+            .worksheet_from_index(self.current_sheet)?
+            .set_name(name)?;
+
+        Ok(())
+    }
+
+    pub fn set_properties(&mut self, properties: &DocProperties) {
+        self.workbook.set_properties(properties);
+    }
+
+    /// 新增一個兩列的工作表，每行爲 (名稱, 值)
+    pub fn add_info_sheet<S: AsRef<str>>(
+        &mut self,
+        name: &str,
+        rows: &[(S, String)],
+    ) -> Result<()> {
+        self.next_sheet();
+        let worksheet = self.workbook.worksheet_from_index(self.current_sheet)?;
+        worksheet.set_name(name)?;
+
+        for (row, (key, value)) in (0..).zip(rows) {
+            worksheet.write(row, 0, key.as_ref())?;
+            worksheet.write(row, 1, value)?;
+        }
+        worksheet.set_column_width(0, 20)?;
+        worksheet.set_column_width(1, 40)?;
+
+        Ok(())
     }
 
-    pub fn save_to_buffer(&mut self) -> Vec<u8> {
-        self.workbook.save_to_buffer().unwrap()
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.workbook.save(path)?;
+
+        Ok(())
+    }
+
+    pub fn save_to_buffer(&mut self) -> Result<Vec<u8>> {
+        Ok(self.workbook.save_to_buffer()?)
+    }
+}
+
+// 字形格子自上而下三行的格式，最後一列的右邊框加粗
+fn cell_formats(last: bool) -> [&'static Format; 3] {
+    if last {
+        [&LAST_TOP_FORMAT, &LAST_MIDDLE_FORMAT, &LAST_BOTTOM_FORMAT]
+    } else {
+        [
+            &MIDDLE_TOP_FORMAT,
+            &MIDDLE_MIDDLE_FORMAT,
+            &MIDDLE_BOTTOM_FORMAT,
+        ]
     }
 }
 
+static HEADER_FORMAT: Lazy<Format> = Lazy::new(|| {
+    Format::new()
+        .set_bold()
+        .set_border(rust_xlsxwriter::FormatBorder::Thick)
+        .set_align(rust_xlsxwriter::FormatAlign::Center)
+        .set_align(rust_xlsxwriter::FormatAlign::VerticalCenter)
+});
 static FIRST_FORMAT: Lazy<Format> = Lazy::new(|| {
     Format::new()
         .set_border_top(rust_xlsxwriter::FormatBorder::Thick)
@@ -137,6 +223,16 @@ static FIRST_FORMAT: Lazy<Format> = Lazy::new(|| {
         .set_align(rust_xlsxwriter::FormatAlign::Center)
         .set_align(rust_xlsxwriter::FormatAlign::VerticalCenter)
 });
+static CHARACTER_FORMAT: Lazy<Format> = Lazy::new(|| {
+    Format::new()
+        .set_font_size(20)
+        .set_border_top(rust_xlsxwriter::FormatBorder::Thick)
+        .set_border_left(rust_xlsxwriter::FormatBorder::Thin)
+        .set_border_bottom(rust_xlsxwriter::FormatBorder::Thick)
+        .set_border_right(rust_xlsxwriter::FormatBorder::Thin)
+        .set_align(rust_xlsxwriter::FormatAlign::Center)
+        .set_align(rust_xlsxwriter::FormatAlign::VerticalCenter)
+});
 static MIDDLE_TOP_FORMAT: Lazy<Format> = Lazy::new(|| {
     Format::new()
         .set_border_top(rust_xlsxwriter::FormatBorder::Thick)